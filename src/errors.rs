@@ -0,0 +1,284 @@
+use std::fmt;
+
+use near_sdk::Balance;
+
+/// Every contract failure, rendered as `E{code}: {message}` so clients can
+/// match on the stable code instead of the wording.
+pub enum Error {
+  Unauthorized,
+  AlreadyInitialized,
+  TokenNotFound,
+  NotTokenOwner,
+  TokenLocked,
+  EvolveCooldown,
+  MaxLevelReached,
+  MetadataLevelMismatch,
+  TokenHasNoMetadata,
+  InvalidTokenExtra,
+  InsufficientDeposit(Balance),
+  SaleNotLive,
+  MintLimitReached(u64),
+  InvalidSaleWindow,
+  WrongWeightCount,
+  NoPositiveWeight,
+  InvalidSpecies,
+  GameContractNotConfigured,
+  TargetLevelTooLow,
+  StageNotReady(u64, u64),
+  AlreadyOwner,
+  InvalidBaseUri,
+  EmptyName,
+  EmptySymbol,
+  ZeroAmount,
+  InsufficientFreeBalance(Balance),
+  EnumerationDisabled,
+  InvalidRecipeSize(usize),
+  RecipeNotFound,
+  DuplicateTokenId,
+  RecipeMismatch,
+  LockerNotApproved,
+  LockNotRequestedByOwner,
+  TokenAlreadyLocked,
+  TokenNotLocked,
+  NotLocker,
+  InvalidReceiver,
+  EmptyRandomSeed,
+  DepositRequired,
+  ApprovalsDisabled,
+  CommitSeedMissing,
+  CommitSeedLocked,
+  MaxSupplyReached(u64),
+  MaxSupplyNotLowered,
+  NotAnEgg,
+  InsufficientStorageCredit(Balance),
+  InvalidBranch,
+  InvalidBatchSize(usize),
+  MetadataFrozen,
+  ApprovalIdMismatch,
+  UnknownLevel(u64),
+  ApprovalExpired,
+  InvalidExpiry,
+  TooManyToSort(usize),
+  InvalidBps,
+  InvalidMetadataType,
+  MissingTitle,
+  MissingEvolutionItem(String, Balance),
+  RedemptionPoolUnderfunded(Balance),
+  MemoTooLong(usize),
+  MetadataStoreMissing,
+  SpeciesNotConfigured(u64),
+  TokenNoLongerExists,
+  TooManyCoOwners(usize),
+  EggNotReady(u64),
+  AlreadyHatched,
+  SpeciesFullyEvolved,
+  RescueNotProposed,
+  RescueNotReady(u64),
+  RescueOwnerChanged,
+  InvalidNickname(usize),
+  InvalidCommitHash,
+  SaleEndRequired,
+  SaleNotEnded,
+  SeedMismatch,
+  SpeciesNotRevealed,
+  SeedNotRevealed,
+  ItemBalanceTooLow(Balance),
+  WeightTotalOverflow,
+}
+
+impl Error {
+  pub fn code(&self) -> &'static str {
+    match self {
+      Error::Unauthorized => "E001",
+      Error::AlreadyInitialized => "E002",
+      Error::TokenNotFound => "E003",
+      Error::NotTokenOwner => "E004",
+      Error::TokenLocked => "E005",
+      Error::EvolveCooldown => "E006",
+      Error::MaxLevelReached => "E007",
+      Error::MetadataLevelMismatch => "E008",
+      Error::TokenHasNoMetadata => "E009",
+      Error::InvalidTokenExtra => "E010",
+      Error::InsufficientDeposit(_) => "E011",
+      Error::SaleNotLive => "E012",
+      Error::MintLimitReached(_) => "E013",
+      Error::InvalidSaleWindow => "E014",
+      Error::WrongWeightCount => "E015",
+      Error::NoPositiveWeight => "E016",
+      Error::InvalidSpecies => "E017",
+      Error::GameContractNotConfigured => "E020",
+      Error::TargetLevelTooLow => "E021",
+      Error::StageNotReady(_, _) => "E022",
+      Error::AlreadyOwner => "E023",
+      Error::InvalidBaseUri => "E024",
+      Error::EmptyName => "E025",
+      Error::EmptySymbol => "E026",
+      Error::ZeroAmount => "E027",
+      Error::InsufficientFreeBalance(_) => "E028",
+      Error::EnumerationDisabled => "E029",
+      Error::InvalidRecipeSize(_) => "E030",
+      Error::RecipeNotFound => "E031",
+      Error::DuplicateTokenId => "E032",
+      Error::RecipeMismatch => "E033",
+      Error::LockerNotApproved => "E034",
+      Error::LockNotRequestedByOwner => "E035",
+      Error::TokenAlreadyLocked => "E036",
+      Error::TokenNotLocked => "E037",
+      Error::NotLocker => "E038",
+      Error::InvalidReceiver => "E040",
+      Error::EmptyRandomSeed => "E041",
+      Error::DepositRequired => "E042",
+      Error::ApprovalsDisabled => "E044",
+      Error::CommitSeedMissing => "E046",
+      Error::CommitSeedLocked => "E047",
+      Error::MaxSupplyReached(_) => "E048",
+      Error::MaxSupplyNotLowered => "E049",
+      Error::NotAnEgg => "E050",
+      Error::InsufficientStorageCredit(_) => "E051",
+      Error::InvalidBranch => "E052",
+      Error::InvalidBatchSize(_) => "E053",
+      Error::MetadataFrozen => "E054",
+      Error::ApprovalIdMismatch => "E055",
+      Error::UnknownLevel(_) => "E056",
+      Error::ApprovalExpired => "E057",
+      Error::InvalidExpiry => "E058",
+      Error::TooManyToSort(_) => "E059",
+      Error::InvalidBps => "E060",
+      Error::InvalidMetadataType => "E061",
+      Error::MissingTitle => "E062",
+      Error::MissingEvolutionItem(_, _) => "E063",
+      Error::RedemptionPoolUnderfunded(_) => "E064",
+      Error::MemoTooLong(_) => "E065",
+      Error::MetadataStoreMissing => "E066",
+      Error::SpeciesNotConfigured(_) => "E067",
+      Error::TokenNoLongerExists => "E068",
+      Error::TooManyCoOwners(_) => "E069",
+      Error::EggNotReady(_) => "E070",
+      Error::AlreadyHatched => "E071",
+      Error::SpeciesFullyEvolved => "E072",
+      Error::RescueNotProposed => "E073",
+      Error::RescueNotReady(_) => "E074",
+      Error::RescueOwnerChanged => "E075",
+      Error::InvalidNickname(_) => "E076",
+      Error::InvalidCommitHash => "E077",
+      Error::SaleEndRequired => "E078",
+      Error::SaleNotEnded => "E079",
+      Error::SeedMismatch => "E080",
+      Error::SpeciesNotRevealed => "E081",
+      Error::SeedNotRevealed => "E082",
+      Error::ItemBalanceTooLow(_) => "E083",
+      Error::WeightTotalOverflow => "E084",
+    }
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}: ", self.code())?;
+
+    match self {
+      Error::Unauthorized => write!(f, "Unauthorized"),
+      Error::AlreadyInitialized => write!(f, "Already Initialized"),
+      Error::TokenNotFound => write!(f, "Token not found"),
+      Error::NotTokenOwner => write!(f, "You are not the Token owner"),
+      Error::TokenLocked => write!(f, "Token is locked"),
+      Error::EvolveCooldown => write!(f, "The evolve time is not fullfiled"),
+      Error::MaxLevelReached => write!(f, "You have reach the maximum level of your monster"),
+      Error::MetadataLevelMismatch => write!(f, "Token metadata does not match its level"),
+      Error::TokenHasNoMetadata => write!(f, "Token has no metadata"),
+      Error::InvalidTokenExtra => write!(f, "Invalid token extra"),
+      Error::InsufficientDeposit(required) => {
+        write!(f, "Must attach {} yoctoNEAR to cover storage", required)
+      }
+      Error::SaleNotLive => write!(f, "The sale is not live"),
+      Error::MintLimitReached(max_mints) => {
+        write!(f, "Mint limit of {} per account reached", max_mints)
+      }
+      Error::InvalidSaleWindow => write!(f, "Sale must start before it ends"),
+      Error::WrongWeightCount => write!(f, "Expected one weight per species"),
+      Error::NoPositiveWeight => write!(f, "At least one species needs a positive weight"),
+      Error::InvalidSpecies => write!(f, "Invalid species"),
+      Error::GameContractNotConfigured => write!(f, "Game contract is not configured"),
+      Error::TargetLevelTooLow => write!(f, "Target level must be above the current level"),
+      Error::StageNotReady(level, ready_at) => {
+        write!(f, "Stage {} is not ready until {}", level, ready_at)
+      }
+      Error::AlreadyOwner => write!(f, "Account is already the owner"),
+      Error::InvalidBaseUri => write!(f, "Invalid base_uri"),
+      Error::EmptyName => write!(f, "Name cannot be empty"),
+      Error::EmptySymbol => write!(f, "Symbol cannot be empty"),
+      Error::ZeroAmount => write!(f, "Amount must be positive"),
+      Error::InsufficientFreeBalance(free_balance) => {
+        write!(f, "Cannot withdraw more than the free balance of {}", free_balance)
+      }
+      Error::EnumerationDisabled => write!(
+        f,
+        "Could not find tokens_per_owner when calling a method on the enumeration standards"
+      ),
+      Error::InvalidRecipeSize(max) => write!(f, "A recipe needs between 1 and {} inputs", max),
+      Error::RecipeNotFound => write!(f, "Recipe not found"),
+      Error::DuplicateTokenId => write!(f, "Duplicate token id in inputs"),
+      Error::RecipeMismatch => write!(f, "Inputs do not match the recipe"),
+      Error::LockerNotApproved => write!(f, "Locker is not approved"),
+      Error::LockNotRequestedByOwner => write!(f, "Lock must be requested by the Token owner"),
+      Error::TokenAlreadyLocked => write!(f, "Token is already locked"),
+      Error::TokenNotLocked => write!(f, "Token is not locked"),
+      Error::NotLocker => write!(f, "Only the locking contract can unlock"),
+      Error::InvalidReceiver => write!(f, "Invalid receiver account id"),
+      Error::EmptyRandomSeed => write!(f, "Random seed is empty"),
+      Error::DepositRequired => write!(f, "Requires attached deposit of at least 1 yoctoNEAR"),
+      Error::ApprovalsDisabled => write!(f, "NFT does not support Approval Management"),
+      Error::CommitSeedMissing => write!(f, "Commit seed is not set"),
+      Error::CommitSeedLocked => write!(f, "Commit seed cannot change in deterministic mode"),
+      Error::MaxSupplyReached(max_supply) => write!(f, "Max supply of {} reached", max_supply),
+      Error::MaxSupplyNotLowered => {
+        write!(f, "Max supply can only be lowered, and not below the minted total")
+      }
+      Error::NotAnEgg => write!(f, "Only unhatched eggs can be rerolled"),
+      Error::InsufficientStorageCredit(credit) => {
+        write!(f, "Storage credit of {} yoctoNEAR is too low", credit)
+      }
+      Error::InvalidBranch => write!(f, "Branch is not configured for this species and level"),
+      Error::InvalidBatchSize(max) => write!(f, "Batches must hold between 1 and {} items", max),
+      Error::MetadataFrozen => write!(f, "Metadata is frozen"),
+      Error::ApprovalIdMismatch => write!(f, "approval_id does not match the current approval"),
+      Error::UnknownLevel(level) => write!(f, "No template for level {}", level),
+      Error::ApprovalExpired => write!(f, "Approval has expired"),
+      Error::InvalidExpiry => write!(f, "Expiry must be in the future"),
+      Error::TooManyToSort(max) => write!(f, "Cannot sort more than {} tokens", max),
+      Error::InvalidBps => write!(f, "Basis points cannot exceed 10000"),
+      Error::InvalidMetadataType => {
+        write!(f, "Metadata type must be egg, monster_{{level}} or a level number")
+      }
+      Error::MissingTitle => write!(f, "Template needs a title"),
+      Error::MissingEvolutionItem(item_contract, amount) => {
+        write!(f, "Evolving needs {} of {} sent with msg \"evolve\"", amount, item_contract)
+      }
+      Error::RedemptionPoolUnderfunded(pool) => {
+        write!(f, "Redemption pool of {} yoctoNEAR cannot cover this redemption", pool)
+      }
+      Error::MemoTooLong(max) => write!(f, "Memo cannot exceed {} bytes", max),
+      Error::MetadataStoreMissing => write!(f, "Token metadata store is not configured"),
+      Error::SpeciesNotConfigured(species) => write!(f, "Species {} is not configured", species),
+      Error::TokenNoLongerExists => write!(f, "Token no longer exists"),
+      Error::TooManyCoOwners(max) => write!(f, "A token can list at most {} co-owners", max),
+      Error::EggNotReady(ready_at) => write!(f, "Egg not ready to hatch until {}", ready_at),
+      Error::AlreadyHatched => write!(f, "Token has already hatched"),
+      Error::SpeciesFullyEvolved => write!(f, "This species is fully evolved"),
+      Error::RescueNotProposed => write!(f, "No rescue is proposed for this token and receiver"),
+      Error::RescueNotReady(ready_at) => write!(f, "Rescue cannot run until {}", ready_at),
+      Error::RescueOwnerChanged => write!(f, "Token owner changed since the rescue was proposed"),
+      Error::InvalidNickname(max) => {
+        write!(f, "Nickname must be at most {} characters with no control characters", max)
+      }
+      Error::InvalidCommitHash => write!(f, "Commitment must be a 32-byte sha256 hash"),
+      Error::SaleEndRequired => write!(f, "Deterministic mode needs a sale end time"),
+      Error::SaleNotEnded => write!(f, "Commit seed can only be revealed after the sale ends"),
+      Error::SeedMismatch => write!(f, "Seed does not match the commitment"),
+      Error::SpeciesNotRevealed => write!(f, "Egg species is not revealed yet"),
+      Error::SeedNotRevealed => write!(f, "Commit seed is not revealed yet"),
+      Error::ItemBalanceTooLow(balance) => write!(f, "Item balance is only {}", balance),
+      Error::WeightTotalOverflow => write!(f, "Species weights must sum to at most u64::MAX"),
+    }
+  }
+}
@@ -0,0 +1,144 @@
+use near_sdk::env;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::Serialize;
+use near_sdk::serde_json;
+use near_sdk::AccountId;
+
+use near_contract_standards::non_fungible_token::TokenId;
+
+const NFT_STANDARD_NAME: &str = "nep171";
+const NFT_STANDARD_VERSION: &str = "1.0.0";
+const NEARMON_STANDARD_NAME: &str = "nearmon";
+const NEARMON_STANDARD_VERSION: &str = "1.0.0";
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+pub enum EventLogVariant {
+  NftMint(Vec<NftMintLog>),
+  NftTransfer(Vec<NftTransferLog>),
+  NftBurn(Vec<NftBurnLog>),
+  NftMetadataUpdate(Vec<NftMetadataUpdateLog>),
+  ContractMetadataUpdate(Vec<ContractMetadataUpdateLog>),
+}
+
+/// NEP-297 envelope, logged as `EVENT_JSON:{...}`.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EventLog {
+  pub standard: String,
+  pub version: String,
+
+  #[serde(flatten)]
+  pub event: EventLogVariant,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftMintLog {
+  pub owner_id: AccountId,
+  pub token_ids: Vec<TokenId>,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub memo: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftTransferLog {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub authorized_id: Option<AccountId>,
+
+  pub old_owner_id: AccountId,
+  pub new_owner_id: AccountId,
+  pub token_ids: Vec<TokenId>,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub memo: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftBurnLog {
+  pub owner_id: AccountId,
+  pub token_ids: Vec<TokenId>,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub memo: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftMetadataUpdateLog {
+  pub token_ids: Vec<TokenId>,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub memo: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractMetadataUpdateLog {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub memo: Option<String>,
+}
+
+pub fn emit(event: EventLogVariant) {
+  let log = EventLog {
+    standard: NFT_STANDARD_NAME.to_string(),
+    version: NFT_STANDARD_VERSION.to_string(),
+    event,
+  };
+
+  env::log(format!("EVENT_JSON:{}", serde_json::to_string(&log).unwrap()).as_bytes());
+}
+
+/// Contract-specific events, logged in the same NEP-297 envelope under the
+/// `nearmon` standard.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+pub enum NearmonEventVariant {
+  TemplateAdded(Vec<TemplateAddedLog>),
+  ApprovalRevoked(Vec<ApprovalRevokedLog>),
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NearmonEventLog {
+  pub standard: String,
+  pub version: String,
+
+  #[serde(flatten)]
+  pub event: NearmonEventVariant,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TemplateAddedLog {
+  pub metadata_type: String,
+  /// sha256 of the borsh-serialized template.
+  pub template_hash: Base64VecU8,
+}
+
+/// NEP-178 has no revoke event; `account_ids` lists every approval the call
+/// removed, so a revoke-all carries them all.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ApprovalRevokedLog {
+  pub owner_id: String,
+  pub token_id: String,
+  pub account_ids: Vec<String>,
+}
+
+pub fn emit_nearmon(event: NearmonEventVariant) {
+  let log = NearmonEventLog {
+    standard: NEARMON_STANDARD_NAME.to_string(),
+    version: NEARMON_STANDARD_VERSION.to_string(),
+    event,
+  };
+
+  env::log(format!("EVENT_JSON:{}", serde_json::to_string(&log).unwrap()).as_bytes());
+}
@@ -1,23 +1,78 @@
 use std::collections::HashMap;
 use near_sdk::borsh::{self, BorshSerialize, BorshDeserialize};
 use near_sdk::collections::{LazyOption, UnorderedSet, UnorderedMap};
-use near_sdk::json_types::{ValidAccountId, U128};
+use near_sdk::json_types::{Base64VecU8, ValidAccountId, U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::{self, Value};
 use near_sdk::{
-  env, near_bindgen, ext_contract, AccountId, PanicOnDefault, 
-  BorshStorageKey, Promise
+  env, near_bindgen, ext_contract, assert_one_yocto, AccountId, Balance, Gas, PanicOnDefault, 
+  BorshStorageKey, Promise, PromiseOrValue, PromiseResult, StorageUsage
 };
 
 use near_contract_standards::non_fungible_token::{Token, TokenId, NonFungibleToken};
+use near_contract_standards::non_fungible_token::core::NonFungibleTokenResolver;
 use near_contract_standards::non_fungible_token::metadata::{
   NFTContractMetadata, NonFungibleTokenMetadataProvider, TokenMetadata, NFT_METADATA_SPEC,
 };
 
+use crate::errors::Error;
+use crate::events::{
+  ApprovalRevokedLog, ContractMetadataUpdateLog, EventLogVariant, NearmonEventVariant, NftBurnLog,
+  NftMetadataUpdateLog, NftMintLog, NftTransferLog, TemplateAddedLog,
+};
+
+mod errors;
+mod events;
 mod metadatas;
+pub mod random;
 
 near_sdk::setup_alloc!();
 
 const MINIMUM_EARLY_DEPOSIT: u128 = 10u128.pow(24);
+const SPECIES_COUNT: u64 = 16;
+// Upper bound for every batch entry point, so none of them can be fed
+// enough items to run out of gas.
+const MAX_BATCH: usize = 50;
+// The one exception to `MAX_BATCH`: each approval in a batch may schedule
+// its own `nft_on_approve` call with `GAS_FOR_NFT_ON_APPROVE`, and 50 of
+// those would not fit in a single call's gas. Same error, lower bound.
+const MAX_APPROVE_BATCH: usize = 10;
+const MAX_PAGE_LIMIT: u64 = 100;
+const EVOLVE_COOLDOWN_MS: u64 = 300000;
+const MAX_FILTER_SCAN: usize = 500;
+const MAX_SORT_SIZE: u64 = 500;
+const MAX_MEMO_LEN: usize = 256;
+const MAX_CO_OWNERS: usize = 10;
+const MAX_NICKNAME_LEN: usize = 32;
+const MAX_BPS: u16 = 10_000;
+// Generous upper bound on the bytes one egg adds across every index.
+const EGG_STORAGE_ESTIMATE_BYTES: u64 = 4_000;
+const NO_DEPOSIT: Balance = 0;
+const RESCUE_TIMELOCK_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+const RESCUE_MEMO: &str = "admin rescue";
+const EVOLVE_ITEM_MSG: &str = "evolve";
+const CONTRACT_SOURCE_VERSION: &str = env!("CARGO_PKG_VERSION");
+const CONTRACT_SOURCE_LINK: &str = "https://github.com/EdoWahdana/nearmon";
+const CONTRACT_STANDARDS: [(&str, &str); 6] = [
+  ("nep171", "1.0.0"),
+  ("nep177", "2.0.0"),
+  ("nep178", "1.0.0"),
+  ("nep181", "1.0.0"),
+  ("nep297", "1.0.0"),
+  ("nep330", "1.1.0"),
+];
+// Overpayments at or below this are kept rather than refunded. A refund
+// receipt costs the contract gas, so a non-zero threshold would trade that
+// gas against the caller's dust; we currently refund every yoctoNEAR.
+const REFUND_DUST_THRESHOLD: Balance = 0;
+const GAS_FOR_GAME_ON_EVOLVE: Gas = 10_000_000_000_000;
+const GAS_FOR_RESOLVE_EVOLVE: Gas = 5_000_000_000_000;
+const GAS_FOR_NFT_ON_APPROVE: Gas = 10_000_000_000_000;
+const GAS_FOR_REGISTRY_ON_MINT: Gas = 10_000_000_000_000;
+const GAS_FOR_RESOLVE_TRANSFER: Gas = 5_000_000_000_000;
+const GAS_FOR_NFT_TRANSFER_CALL: Gas = 25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER;
+const GAS_FOR_FT_TRANSFER: Gas = 10_000_000_000_000;
+const GAS_FOR_RESOLVE_ITEM_WITHDRAW: Gas = 5_000_000_000_000;
 pub type MetadataType = String;
 
 #[derive(Serialize, Deserialize)]
@@ -28,6 +83,145 @@ pub struct JsonToken {
   pub metadata: TokenMetadata,
 }
 
+/// Stored as JSON in `TokenMetadata.extra`: when the token may evolve next
+/// and the game attributes taken from its template's own `extra`.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenExtra {
+  pub evolve_at: u64,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub attrs: Option<Value>,
+}
+
+impl TokenExtra {
+  /// Tokens minted before attributes existed store only the bare timestamp.
+  pub fn parse(extra: &str) -> Self {
+    serde_json::from_str(extra).unwrap_or_else(|_| TokenExtra {
+      evolve_at: extra.parse().unwrap_or_else(|_| panic!("{}", Error::InvalidTokenExtra)),
+      attrs: None,
+    })
+  }
+}
+
+/// A token's game state in one place, so clients never parse `extra`.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GameAttrs {
+  pub species: u64,
+  pub level: u64,
+  /// Milliseconds.
+  pub evolve_at: U64,
+  pub attrs: Option<Value>,
+}
+
+/// One page of an owner's tokens plus how many they hold in total.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenPage {
+  pub total: U128,
+  pub tokens: Vec<Token>,
+}
+
+/// A token plus the contract-side state a roster screen shows next to it.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DetailedToken {
+  #[serde(flatten)]
+  pub token: Token,
+  pub game: Option<GameAttrs>,
+  pub nickname: Option<String>,
+}
+
+/// Every owner-configurable setting, read in one call. Balances are in
+/// yoctoNEAR and times in milliseconds.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractConfig {
+  pub owner_id: AccountId,
+  pub mint_fee: U128,
+  pub evolve_fee: U128,
+  pub reroll_fee: U128,
+  pub max_supply: Option<U64>,
+  pub max_mints_per_account: Option<u64>,
+  pub sale_start_ms: Option<U64>,
+  pub sale_end_ms: Option<U64>,
+  pub species_count: u64,
+  pub deterministic_mint: bool,
+  pub metadata_frozen: bool,
+  pub game_contract_id: Option<AccountId>,
+  pub registry_contract: Option<AccountId>,
+  pub renderer_base: Option<String>,
+  pub treasury: Option<AccountId>,
+  pub treasury_bps: u16,
+  pub base_cooldown_ms: Option<U64>,
+  pub cooldown_multiplier_bps: u32,
+  pub templates_required: bool,
+  pub default_media: Option<String>,
+}
+
+/// One species as a client lists it before any token is loaded.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SpeciesEntry {
+  pub species: u64,
+  pub weight: u64,
+  pub supply: U128,
+  pub max_level: Option<u64>,
+}
+
+/// Everything a client needs on cold start, from one view call.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Bootstrap {
+  pub metadata: NFTContractMetadata,
+  pub owner_id: AccountId,
+  pub config: ContractConfig,
+  pub total_supply: U128,
+  pub species: Vec<SpeciesEntry>,
+}
+
+/// NEP-330 source metadata.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractSourceMetadata {
+  pub version: Option<String>,
+  pub link: Option<String>,
+  pub standards: Vec<Standard>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Standard {
+  pub standard: String,
+  pub version: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RecipeInput {
+  pub species: u64,
+  pub level: u64,
+}
+
+/// Burning tokens matching every entry of `inputs` (as a multiset) crafts an
+/// egg of `output_species`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Recipe {
+  pub inputs: Vec<RecipeInput>,
+  pub output_species: u64,
+}
+
+/// A pending move of a token whose owner can no longer sign, e.g. because
+/// the account was deleted.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenRescue {
+  pub stuck_owner_id: AccountId,
+  pub new_owner_id: AccountId,
+  pub ready_at: u64,
+}
+
 #[near_bindgen]
 #[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
 pub struct Contract {
@@ -36,8 +230,87 @@ pub struct Contract {
   metadata_per_type: UnorderedMap<MetadataType, UnorderedSet<TokenMetadata>>,
   egg_per_token_id: UnorderedMap<TokenId, u64>,
   level_per_token_id: UnorderedMap<TokenId, u64>,
+  tokens_per_species: UnorderedMap<u64, UnorderedSet<TokenId>>,
+  tokens_per_level: UnorderedMap<u64, UnorderedSet<TokenId>>,
+  copies_per_template: UnorderedMap<(u64, u64), u64>,
+  lineage_per_token_id: UnorderedMap<TokenId, TokenId>,
+  recipes: UnorderedMap<u64, Recipe>,
+  approved_lockers: UnorderedSet<AccountId>,
+  locked_tokens: UnorderedMap<TokenId, AccountId>,
+  max_mints_per_account: Option<u64>,
+  // One weight per species, indexed by `species - 1`.
+  species_weights: Vec<u64>,
+  mints_per_account: UnorderedMap<AccountId, u64>,
+  // Keyed by the lineage's original egg id.
+  minted_at_per_lineage: UnorderedMap<TokenId, u64>,
+  // Append-only: (previous owner, handover timestamp in ms) per change.
+  owner_history: UnorderedMap<u64, (AccountId, u64)>,
+  xp_per_token_id: UnorderedMap<TokenId, u64>,
+  // Only the sha256 commitment is stored until the sale ends; the seed
+  // itself would be readable through `view_state`.
+  commit_seed_hash: Option<Vec<u8>>,
+  revealed_seed: Option<String>,
+  deterministic_mint: bool,
+  // Eggs minted in deterministic mode before the reveal; their species is
+  // provisional until `resolve_revealed_eggs` rolls it from the seed.
+  unrevealed_eggs: UnorderedSet<TokenId>,
+  // Milliseconds; `None` leaves that side of the sale window open.
+  sale_start_ms: Option<u64>,
+  sale_end_ms: Option<u64>,
+  supply_per_species: UnorderedMap<u64, u64>,
   metadata: LazyOption<NFTContractMetadata>,
-  current_token_id: TokenId,
+  current_token_id: u64,
+  game_contract_id: Option<AccountId>,
+  evolve_fee: Balance,
+  // Tokens held per account; accounts holding none have no entry, so its
+  // length is the number of distinct owners.
+  count_per_owner: UnorderedMap<AccountId, u64>,
+  max_supply: Option<u64>,
+  // Lifetime egg mints; evolving and burning leave it untouched.
+  total_minted: u64,
+  renderer_base: Option<String>,
+  reroll_fee: Balance,
+  storage_credits: UnorderedMap<AccountId, Balance>,
+  // Sum of `storage_credits`, kept in step by `set_storage_credit`.
+  total_storage_credits: Balance,
+  // Keyed by (species, level).
+  evolve_branches: UnorderedMap<(u64, u64), Vec<TokenMetadata>>,
+  // Index into `evolve_branches` picked when the token evolved into its
+  // current level; absent for tokens on the default path.
+  branch_per_token: UnorderedMap<TokenId, u64>,
+  metadata_frozen: bool,
+  // Per token: approved account -> (approval id, expiry in ms). An entry
+  // only applies while that approval id is still the current one.
+  approval_expiry_by_id: UnorderedMap<TokenId, HashMap<AccountId, (u64, u64)>>,
+  supply_per_level: UnorderedMap<u64, u64>,
+  registry_contract: Option<AccountId>,
+  treasury: Option<AccountId>,
+  // Share of each mint fee, net of storage, sent to `treasury`.
+  treasury_bps: u16,
+  // Fungible token and amount consumed to evolve into a level.
+  required_item_per_level: UnorderedMap<u64, (AccountId, Balance)>,
+  // Deposited evolution items, keyed by (player, item contract).
+  item_balances: UnorderedMap<(AccountId, AccountId), Balance>,
+  // Cooldown at level L is `base * (multiplier_bps / 10000)^L`; `None`
+  // keeps the flat `EVOLVE_COOLDOWN_MS`.
+  base_cooldown_ms: Option<u64>,
+  cooldown_multiplier_bps: u32,
+  // NEAR set aside for `burn_to_redeem`; `admin_withdraw` cannot touch it.
+  redemption_pool: Balance,
+  redemption_per_level: UnorderedMap<u64, Balance>,
+  // Eggs only mint from registered templates, never the built-in ones.
+  templates_required: bool,
+  // Display-only annotation set by the token owner; grants no rights and
+  // is dropped whenever the token changes hands.
+  co_owners_per_token: UnorderedMap<TokenId, Vec<AccountId>>,
+  // `media` for tokens whose template has none and no renderer is set.
+  default_media: Option<String>,
+  // Highest level a species can reach; unset species go as far as the
+  // templates allow.
+  max_level_per_species: UnorderedMap<u64, u64>,
+  rescue_per_token: UnorderedMap<TokenId, TokenRescue>,
+  // Follows the token through transfers and evolutions.
+  nickname_per_token: UnorderedMap<TokenId, String>,
 }
 
 #[derive(BorshSerialize, BorshStorageKey)]
@@ -49,26 +322,167 @@ enum StorageKey {
   TokensPerOwner { account_hash: Vec<u8> },
   MetadataPerType,
   Metadata,
-  MetadataPerTypeInner,
+  MetadataPerTypeInner { metadata_type_hash: Vec<u8> },
   EggPerTokenId,
   LevelPerTokenId,
+  TokensPerSpecies,
+  TokensPerSpeciesInner { species: u64 },
+  TokensPerLevel,
+  TokensPerLevelInner { level: u64 },
+  CopiesPerTemplate,
+  SupplyPerSpecies,
+  LineagePerTokenId,
+  Recipes,
+  ApprovedLockers,
+  LockedTokens,
+  MintsPerAccount,
+  MintedAtPerLineage,
+  OwnerHistory,
+  XpPerTokenId,
+  StorageCredits,
+  EvolveBranches,
+  CountPerOwner,
+  ApprovalExpiry,
+  SupplyPerLevel,
+  RequiredItemPerLevel,
+  ItemBalances,
+  RedemptionPerLevel,
+  CoOwnersPerToken,
+  MaxLevelPerSpecies,
+  RescuePerToken,
+  NicknamePerToken,
+  UnrevealedEggs,
+  BranchPerToken,
+}
+
+#[cfg(feature = "debug")]
+impl StorageKey {
+  /// Every variant with a readable name, in declaration order. Variants
+  /// with fields get placeholder values; only their leading tag byte is
+  /// fixed.
+  fn layout() -> Vec<(&'static str, StorageKey)> {
+    vec![
+      ("NonFungibleToken", StorageKey::NonFungibleToken),
+      ("TokenMetadata", StorageKey::TokenMetadata),
+      ("Enumeration", StorageKey::Enumeration),
+      ("Approval", StorageKey::Approval),
+      ("TokensPerOwner { account_hash: .. }", StorageKey::TokensPerOwner { account_hash: vec![] }),
+      ("MetadataPerType", StorageKey::MetadataPerType),
+      ("Metadata", StorageKey::Metadata),
+      ("MetadataPerTypeInner { metadata_type_hash: .. }", StorageKey::MetadataPerTypeInner { metadata_type_hash: vec![] }),
+      ("EggPerTokenId", StorageKey::EggPerTokenId),
+      ("LevelPerTokenId", StorageKey::LevelPerTokenId),
+      ("TokensPerSpecies", StorageKey::TokensPerSpecies),
+      ("TokensPerSpeciesInner { species: .. }", StorageKey::TokensPerSpeciesInner { species: 0 }),
+      ("TokensPerLevel", StorageKey::TokensPerLevel),
+      ("TokensPerLevelInner { level: .. }", StorageKey::TokensPerLevelInner { level: 0 }),
+      ("CopiesPerTemplate", StorageKey::CopiesPerTemplate),
+      ("SupplyPerSpecies", StorageKey::SupplyPerSpecies),
+      ("LineagePerTokenId", StorageKey::LineagePerTokenId),
+      ("Recipes", StorageKey::Recipes),
+      ("ApprovedLockers", StorageKey::ApprovedLockers),
+      ("LockedTokens", StorageKey::LockedTokens),
+      ("MintsPerAccount", StorageKey::MintsPerAccount),
+      ("MintedAtPerLineage", StorageKey::MintedAtPerLineage),
+      ("OwnerHistory", StorageKey::OwnerHistory),
+      ("XpPerTokenId", StorageKey::XpPerTokenId),
+      ("StorageCredits", StorageKey::StorageCredits),
+      ("EvolveBranches", StorageKey::EvolveBranches),
+      ("CountPerOwner", StorageKey::CountPerOwner),
+      ("ApprovalExpiry", StorageKey::ApprovalExpiry),
+      ("SupplyPerLevel", StorageKey::SupplyPerLevel),
+      ("RequiredItemPerLevel", StorageKey::RequiredItemPerLevel),
+      ("ItemBalances", StorageKey::ItemBalances),
+      ("RedemptionPerLevel", StorageKey::RedemptionPerLevel),
+      ("CoOwnersPerToken", StorageKey::CoOwnersPerToken),
+      ("MaxLevelPerSpecies", StorageKey::MaxLevelPerSpecies),
+      ("RescuePerToken", StorageKey::RescuePerToken),
+      ("NicknamePerToken", StorageKey::NicknamePerToken),
+      ("UnrevealedEggs", StorageKey::UnrevealedEggs),
+      ("BranchPerToken", StorageKey::BranchPerToken),
+    ]
+  }
 }
 
 pub trait NonFungibleTokenCore {
   fn nft_approve(&mut self, token_id: TokenId, account_id: AccountId, msg: Option<String>);
 
-  fn nft_is_approved(&self, token_id: TokenId, approved_account_id: AccountId, approval_id: Option<u64>);
+  fn nft_is_approved(&self, token_id: TokenId, approved_account_id: AccountId, approval_id: Option<u64>) -> bool;
 
   fn nft_revoke(&mut self, token_id: TokenId, account_id: AccountId);
 
   fn nft_revoke_all(&mut self, token_id: TokenId);
 }
 
+/// Source of entropy used when rolling the species of a new egg.
+pub trait RandomSource {
+  fn random_seed(&self) -> Vec<u8>;
+}
+
+/// The only source reachable from contract methods: the block random seed.
+pub struct BlockRandomSource;
+
+impl RandomSource for BlockRandomSource {
+  fn random_seed(&self) -> Vec<u8> {
+    env::random_seed()
+  }
+}
+
+/// Provably fair source: `sha256(seed ++ token_id)`, reproducible by anyone
+/// once the seed is revealed.
+pub struct CommitSeedRandomSource {
+  pub seed: String,
+  pub token_id: TokenId,
+}
+
+impl RandomSource for CommitSeedRandomSource {
+  fn random_seed(&self) -> Vec<u8> {
+    let mut input = self.seed.as_bytes().to_vec();
+    input.extend_from_slice(self.token_id.as_bytes());
+    env::sha256(&input)
+  }
+}
+
 #[ext_contract(ext_non_fungible_approval_receiver)]
 trait NonFungibleTokenApprovalsReceiver {
   fn nft_on_approve(&mut self, token_id: TokenId, owner_id: AccountId, approval_id: u64, msg: String);
 }
 
+#[ext_contract(ext_non_fungible_token_receiver)]
+trait NonFungibleTokenReceiver {
+  fn nft_on_transfer(&mut self, sender_id: AccountId, previous_owner_id: AccountId, token_id: TokenId, msg: String) -> PromiseOrValue<bool>;
+}
+
+#[ext_contract(ext_game)]
+trait GameContract {
+  fn on_nft_evolve(&mut self, owner_id: AccountId, previous_token_id: TokenId, token_id: TokenId, level: u64, msg: String);
+}
+
+#[ext_contract(ext_registry)]
+trait RegistryContract {
+  fn on_nft_minted(&mut self, owner_id: AccountId, token_id: TokenId, species: u64);
+}
+
+#[ext_contract(ext_fungible_token)]
+trait FungibleToken {
+  fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+#[ext_contract(ext_self)]
+trait EvolveResolver {
+  fn nft_resolve_evolve(&mut self, owner_id: AccountId, token_id: TokenId) -> bool;
+
+  fn resolve_item_withdraw(&mut self, account_id: AccountId, item_contract: AccountId, amount: U128) -> bool;
+
+  fn nft_resolve_transfer(
+    &mut self,
+    previous_owner_id: AccountId,
+    receiver_id: AccountId,
+    token_id: TokenId,
+    approved_account_ids: Option<HashMap<AccountId, u64>>,
+  ) -> bool;
+}
+
 #[near_bindgen]
 impl Contract {
   #[init]
@@ -84,6 +498,7 @@ impl Contract {
         reference: None,
         reference_hash: None,
       },
+      None,
     )
   }
 
@@ -91,8 +506,9 @@ impl Contract {
   pub fn new(
     owner_id: ValidAccountId,
     metadata: NFTContractMetadata,
+    max_supply: Option<u64>,
   ) -> Self {
-    assert!(!env::state_exists(), "Already Initialized");
+    assert!(!env::state_exists(), "{}", Error::AlreadyInitialized);
     metadata.assert_valid();
     let owner = owner_id.to_string();
     Self {
@@ -106,350 +522,7484 @@ impl Contract {
       ),
       egg_per_token_id: UnorderedMap::new(StorageKey::EggPerTokenId),
       level_per_token_id: UnorderedMap::new(StorageKey::LevelPerTokenId),
+      tokens_per_species: UnorderedMap::new(StorageKey::TokensPerSpecies),
+      tokens_per_level: UnorderedMap::new(StorageKey::TokensPerLevel),
+      copies_per_template: UnorderedMap::new(StorageKey::CopiesPerTemplate),
+      lineage_per_token_id: UnorderedMap::new(StorageKey::LineagePerTokenId),
+      recipes: UnorderedMap::new(StorageKey::Recipes),
+      approved_lockers: UnorderedSet::new(StorageKey::ApprovedLockers),
+      locked_tokens: UnorderedMap::new(StorageKey::LockedTokens),
+      max_mints_per_account: None,
+      species_weights: vec![1; SPECIES_COUNT as usize],
+      mints_per_account: UnorderedMap::new(StorageKey::MintsPerAccount),
+      minted_at_per_lineage: UnorderedMap::new(StorageKey::MintedAtPerLineage),
+      owner_history: UnorderedMap::new(StorageKey::OwnerHistory),
+      xp_per_token_id: UnorderedMap::new(StorageKey::XpPerTokenId),
+      commit_seed_hash: None,
+      revealed_seed: None,
+      deterministic_mint: false,
+      unrevealed_eggs: UnorderedSet::new(StorageKey::UnrevealedEggs),
+      sale_start_ms: None,
+      sale_end_ms: None,
+      supply_per_species: UnorderedMap::new(StorageKey::SupplyPerSpecies),
       metadata_per_type: UnorderedMap::new(StorageKey::MetadataPerType),
       metadata: LazyOption::new(
         StorageKey::Metadata.try_to_vec().unwrap(),
         Some(&metadata),
       ),
-      current_token_id: String::from("0"),
+      current_token_id: 0,
+      game_contract_id: None,
+      evolve_fee: MINIMUM_EARLY_DEPOSIT,
+      count_per_owner: UnorderedMap::new(StorageKey::CountPerOwner),
+      max_supply,
+      total_minted: 0,
+      renderer_base: None,
+      reroll_fee: MINIMUM_EARLY_DEPOSIT,
+      storage_credits: UnorderedMap::new(StorageKey::StorageCredits),
+      total_storage_credits: 0,
+      evolve_branches: UnorderedMap::new(StorageKey::EvolveBranches),
+      branch_per_token: UnorderedMap::new(StorageKey::BranchPerToken),
+      metadata_frozen: false,
+      approval_expiry_by_id: UnorderedMap::new(StorageKey::ApprovalExpiry),
+      supply_per_level: UnorderedMap::new(StorageKey::SupplyPerLevel),
+      registry_contract: None,
+      treasury: None,
+      treasury_bps: 0,
+      required_item_per_level: UnorderedMap::new(StorageKey::RequiredItemPerLevel),
+      item_balances: UnorderedMap::new(StorageKey::ItemBalances),
+      base_cooldown_ms: None,
+      cooldown_multiplier_bps: MAX_BPS as u32,
+      redemption_pool: 0,
+      redemption_per_level: UnorderedMap::new(StorageKey::RedemptionPerLevel),
+      templates_required: false,
+      co_owners_per_token: UnorderedMap::new(StorageKey::CoOwnersPerToken),
+      default_media: None,
+      max_level_per_species: UnorderedMap::new(StorageKey::MaxLevelPerSpecies),
+      rescue_per_token: UnorderedMap::new(StorageKey::RescuePerToken),
+      nickname_per_token: UnorderedMap::new(StorageKey::NicknamePerToken),
     }
   }
 
+  /// Registers a template for `egg`, `monster_{level}` or a bare level.
+  /// Templates of a level go to species in the order they are added: the
+  /// first distinct one is species 1's, the second species 2's, and so on.
+  /// A species without one of its own keeps the built-in stage (or cannot
+  /// reach the level without one) and, with `templates_required`, counts as
+  /// not configured.
   #[payable]
   pub fn add_metadata(
     &mut self,
     metadata_type: MetadataType,
     metadata: TokenMetadata,
   ) {
-    let caller_id = env::signer_account_id();
-    let lower_type = metadata_type.to_lowercase();
-
-    assert_eq!(
-      caller_id,
-      self.owner_id,
-      "Unauthorized",
+    self.assert_owner();
+    self.assert_metadata_not_frozen();
+    let lower_type = canonical_metadata_type(&metadata_type)
+      .unwrap_or_else(|| panic!("{}", Error::InvalidMetadataType));
+    assert!(
+      metadata.title.as_ref().map_or(false, |title| !title.trim().is_empty()),
+      "{}",
+      Error::MissingTitle
     );
 
     let mut metadata_set = self.metadata_per_type.get(&lower_type).unwrap_or_else(|| {
-      UnorderedSet::new(StorageKey::MetadataPerTypeInner)
+      UnorderedSet::new(StorageKey::MetadataPerTypeInner {
+        metadata_type_hash: env::sha256(&lower_type.as_bytes()),
+      })
     });
 
-    metadata_set.insert(&metadata);
+    let added = metadata_set.insert(&metadata);
 
     self.metadata_per_type.insert(&lower_type, &metadata_set);
+
+    if added {
+      events::emit_nearmon(NearmonEventVariant::TemplateAdded(vec![TemplateAddedLog {
+        metadata_type: lower_type,
+        template_hash: Base64VecU8(env::sha256(&metadata.try_to_vec().unwrap())),
+      }]));
+    }
   }
 
   #[payable]
   pub fn nft_mint_egg(
     &mut self,
     receiver_id: AccountId,
+    memo: Option<String>,
   ) {
-    self.increment_token_id();
-
-    let metadata_type = 0u64;
-    let owner_id: AccountId = receiver_id;
-    let mut metadata_set = self.get_random_number() as u64;
-    metadata_set = metadata_set % 16 + 1;
+    assert_memo_len(&memo);
+    if env::predecessor_account_id() != self.owner_id {
+      assert_eq!(self.sale_status(), "live", "{}", Error::SaleNotLive);
+    }
 
-    let metadata: TokenMetadata = self.get_metadata_per_type(metadata_type, metadata_set);
+    let initial_storage_usage = env::storage_usage();
+    let minter_id = env::predecessor_account_id();
+    let minted = self.mints_per_account.get(&minter_id).unwrap_or(0);
+    if let Some(max_mints) = self.max_mints_per_account {
+      assert!(minted < max_mints, "{}", Error::MintLimitReached(max_mints));
+    }
+    self.mints_per_account.insert(&minter_id, &(minted + 1));
 
-    self.tokens.owner_by_id.insert(&self.current_token_id, &owner_id);
+    let token_id = if self.deterministic_mint {
+      match self.revealed_seed.clone() {
+        Some(seed) => {
+          let random_source = CommitSeedRandomSource {
+            seed,
+            token_id: (self.current_token_id + 1).to_string(),
+          };
+          self.internal_mint_egg(receiver_id.clone(), &random_source)
+        }
+        None => {
+          let token_id = self.internal_mint_egg(receiver_id.clone(), &BlockRandomSource);
+          self.unrevealed_eggs.insert(&token_id);
+          token_id
+        }
+      }
+    } else {
+      self.internal_mint_egg(receiver_id.clone(), &BlockRandomSource)
+    };
 
-    self.egg_per_token_id.insert(&self.current_token_id, &metadata_set);
+    self.charge_with_storage_credit(&minter_id, MINIMUM_EARLY_DEPOSIT);
+    let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+    self.pay_treasury(MINIMUM_EARLY_DEPOSIT, storage_used);
 
-    self.level_per_token_id.insert(&self.current_token_id, &0u64);
-    
-    self.tokens
-      .token_metadata_by_id
-      .as_mut()
-      .and_then(|by_id| by_id.insert(&self.current_token_id, &metadata));
+    events::emit(EventLogVariant::NftMint(vec![NftMintLog {
+      owner_id: receiver_id.clone(),
+      token_ids: vec![token_id.clone()],
+      memo,
+    }]));
 
-    if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
-      let mut token_ids = tokens_per_owner.get(&owner_id).unwrap_or_else(|| {
-        UnorderedSet::new(StorageKey::TokensPerOwner {
-          account_hash: env::sha256(&owner_id.as_bytes()),
-        })
-      });
-      token_ids.insert(&self.current_token_id);
-      tokens_per_owner.insert(&owner_id, &token_ids);
+    // Fire-and-forget: the mint is already committed, so a failing registry
+    // cannot undo it.
+    if let Some(registry_contract) = &self.registry_contract {
+      let species = self.egg_per_token_id.get(&token_id).unwrap();
+      ext_registry::on_nft_minted(
+        receiver_id,
+        token_id,
+        species,
+        registry_contract,
+        NO_DEPOSIT,
+        GAS_FOR_REGISTRY_ON_MINT,
+      );
     }
+  }
 
-    refund_deposit();
+  /// Deposit to attach to `nft_mint_egg`. Never below what the mint enforces,
+  /// and padded so a larger-than-usual egg still fits.
+  pub fn mint_cost_estimate(&self) -> U128 {
+    let storage_cost = env::storage_byte_cost() * Balance::from(EGG_STORAGE_ESTIMATE_BYTES);
+    U128(MINIMUM_EARLY_DEPOSIT.max(storage_cost))
   }
 
+  /// Pre-pays mints: later mints by the caller draw from this credit before
+  /// asking for an attached deposit.
   #[payable]
-  pub fn nft_evolve(
-    &mut self,
-    token_id: TokenId,
-    receiver_id: AccountId,
-  ) {    
-    self.increment_token_id();
-    let evolve_time;
-
-    if let Some(temp_metadata) = &self.tokens.token_metadata_by_id {
-      evolve_time = temp_metadata.get(&token_id).unwrap().extra.unwrap();
-      
-      if env::block_timestamp() / 1000000 < evolve_time.parse::<u64>().unwrap() {
-        panic!("The evolve time is not fullfiled");
-      }
-    }
+  pub fn deposit_storage(&mut self) -> U128 {
+    let account_id = env::predecessor_account_id();
+    let credit = self.storage_credits.get(&account_id).unwrap_or(0) + env::attached_deposit();
+    self.set_storage_credit(&account_id, credit);
+    U128(credit)
+  }
 
-    let owner_id = self.tokens.owner_by_id.get(&token_id).unwrap();
-    assert_eq!(
-      owner_id,
-      env::predecessor_account_id(),
-      "You are not the Token owner",
-    );
+  pub fn storage_credit_of(
+    &self,
+    account_id: AccountId,
+  ) -> U128 {
+    U128(self.storage_credits.get(&account_id).unwrap_or(0))
+  }
 
-    if let Some(next_approval_id_by_id) = &mut self.tokens.next_approval_id_by_id {
-      next_approval_id_by_id.remove(&token_id);
-    }
+  #[payable]
+  pub fn withdraw_storage_credit(
+    &mut self,
+    amount: U128,
+  ) -> Promise {
+    assert_one_yocto();
+    let account_id = env::predecessor_account_id();
+    let amount: Balance = amount.into();
+    let credit = self.storage_credits.get(&account_id).unwrap_or(0);
+    assert!(amount > 0, "{}", Error::ZeroAmount);
+    assert!(amount <= credit, "{}", Error::InsufficientStorageCredit(credit));
 
-    if let Some(approvals_by_id) = &mut self.tokens.approvals_by_id {
-      approvals_by_id.remove(&token_id);
-    }
+    self.set_storage_credit(&account_id, credit - amount);
+    Promise::new(account_id).transfer(amount)
+  }
 
-    if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
-      let mut token_set = tokens_per_owner.get(&receiver_id).unwrap();
-      token_set.remove(&token_id);
-      tokens_per_owner.insert(&receiver_id, &token_set);
-    } 
+  pub fn lower_max_supply(
+    &mut self,
+    max_supply: u64,
+  ) {
+    self.assert_owner();
+    assert!(
+      self.max_supply.map_or(true, |current| max_supply < current) && max_supply >= self.total_minted,
+      "{}",
+      Error::MaxSupplyNotLowered,
+    );
 
-    if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
-      token_metadata_by_id.remove(&token_id);
-    }
+    self.max_supply = Some(max_supply);
+  }
 
-    let metadata_set = self.egg_per_token_id.get(&token_id).unwrap();
-    self.egg_per_token_id.insert(&self.current_token_id, &metadata_set);
+  pub fn get_max_supply(&self) -> Option<U64> {
+    self.max_supply.map(U64)
+  }
 
-    let mut token_level = self.level_per_token_id.get(&token_id).unwrap();
-    token_level = token_level + 1;
-    self.level_per_token_id.remove(&token_id);
-    self.level_per_token_id.insert(&self.current_token_id, &token_level);    
+  pub fn total_minted(&self) -> U64 {
+    U64(self.total_minted)
+  }
 
-    let metadata: TokenMetadata = self.get_metadata_per_type(token_level, metadata_set);
-    self.tokens.owner_by_id.insert(&self.current_token_id, &owner_id);
+  /// The id the next mint, evolve or craft will assign. Any of those landing
+  /// first takes it, so treat this as a hint for optimistic UI only.
+  pub fn next_token_id(&self) -> TokenId {
+    (self.current_token_id + 1).to_string()
+  }
 
-    self.tokens
-      .token_metadata_by_id
-      .as_mut()
-      .and_then(|by_id| by_id.insert(&self.current_token_id, &metadata));
+  /// Commits to the drop's seed before the sale by its sha256 hash; the
+  /// seed itself never touches the chain until `reveal_commit_seed`. The
+  /// commitment is frozen while deterministic mode is on.
+  pub fn set_commit_seed_hash(
+    &mut self,
+    seed_hash: Base64VecU8,
+  ) {
+    self.assert_owner();
+    assert!(!self.deterministic_mint, "{}", Error::CommitSeedLocked);
+    assert_eq!(seed_hash.0.len(), 32, "{}", Error::InvalidCommitHash);
+    self.commit_seed_hash = Some(seed_hash.into());
+    self.revealed_seed = None;
+  }
 
-    if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
-      let mut token_ids = tokens_per_owner.get(&owner_id).unwrap_or_else(|| {
-        UnorderedSet::new(StorageKey::TokensPerOwner {
-          account_hash: env::sha256(&owner_id.as_bytes()),
-        })
-      });
-      token_ids.insert(&self.current_token_id);
-      tokens_per_owner.insert(&owner_id, &token_ids);
+  /// In deterministic mode eggs are minted with a provisional species and
+  /// rolled from `CommitSeedRandomSource` once the seed is revealed. The
+  /// reveal happens when the sale ends, so enabling it needs a sale end.
+  pub fn set_deterministic_mint(
+    &mut self,
+    enabled: bool,
+  ) {
+    self.assert_owner();
+    if enabled {
+      assert!(self.commit_seed_hash.is_some(), "{}", Error::CommitSeedMissing);
+      assert!(self.sale_end_ms.is_some(), "{}", Error::SaleEndRequired);
     }
+    self.deterministic_mint = enabled;
+  }
 
-    refund_deposit()
+  pub fn commit_seed_hash(&self) -> Option<Base64VecU8> {
+    self.commit_seed_hash.clone().map(Base64VecU8)
   }
 
-  pub fn nft_tokens_for_owner(
-    &self,
-    account_id: AccountId,
-    from_index: Option<U128>,
-    limit: Option<u64>
-  ) -> Vec<Token> {
-    let tokens_per_owner = self.tokens.tokens_per_owner.as_ref().expect(
-      "Could not find tokens_per_owner when calling a method on the enumeration standards",
-    );
+  /// Publishes the seed once the sale has ended, checked against the
+  /// commitment. Unrevealed eggs are then rolled by `resolve_revealed_eggs`.
+  pub fn reveal_commit_seed(
+    &mut self,
+    seed: String,
+  ) {
+    self.assert_owner();
+    let seed_hash = self.commit_seed_hash
+      .as_ref()
+      .unwrap_or_else(|| panic!("{}", Error::CommitSeedMissing));
+    assert_eq!(self.sale_status(), "ended", "{}", Error::SaleNotEnded);
+    assert_eq!(&env::sha256(seed.as_bytes()), seed_hash, "{}", Error::SeedMismatch);
+    self.revealed_seed = Some(seed);
+  }
 
-    let token_set = if let Some(token_set) = tokens_per_owner.get(&account_id) {
-      token_set
-    } else {
-      return vec![];
-    };
+  pub fn revealed_commit_seed(&self) -> Option<String> {
+    self.revealed_seed.clone()
+  }
 
-    let keys = token_set.as_vector();
+  /// Rolls up to `limit` (capped at `MAX_BATCH`) unrevealed eggs from the
+  /// revealed seed and their token id, and returns how many remain.
+  /// Anyone may call it, since the outcome is fixed by the seed.
+  pub fn resolve_revealed_eggs(
+    &mut self,
+    limit: Option<u64>,
+  ) -> u64 {
+    let seed = self.revealed_seed
+      .clone()
+      .unwrap_or_else(|| panic!("{}", Error::SeedNotRevealed));
+    let limit = limit.unwrap_or(MAX_BATCH as u64).min(MAX_BATCH as u64) as usize;
 
-    let start = u128::from(from_index.unwrap_or(U128(0)));
+    let token_ids: Vec<TokenId> = self.unrevealed_eggs.iter().take(limit).collect();
+    for token_id in token_ids {
+      let random_source = CommitSeedRandomSource {
+        seed: seed.clone(),
+        token_id: token_id.clone(),
+      };
+      let species = self.roll_species(&random_source);
+      self.internal_set_egg_species(&token_id, species);
+      self.unrevealed_eggs.remove(&token_id);
+    }
 
-    keys
-      .iter()
-      .skip(start as usize)
-      .take(limit.unwrap_or(0) as usize)
-      .map(|token| self.nft_token(token).unwrap())
-      .collect()
+    self.unrevealed_eggs.len()
   }
 
-  pub fn nft_token(
-    &self, 
+  pub fn is_species_revealed(
+    &self,
     token_id: TokenId,
-  ) -> Option<Token> {
-    let owner_id = self.tokens.owner_by_id.get(&token_id)?;
-    let approved_account_ids = self
-      .tokens
-      .approvals_by_id
-      .as_ref()
-      .and_then(|by_id| by_id.get(&token_id).or_else(|| Some(HashMap::new())));
+  ) -> bool {
+    !self.unrevealed_eggs.contains(&token_id)
+  }
 
-    let token_metadata = self.tokens.token_metadata_by_id.as_ref().unwrap().get(&token_id).unwrap();
+  /// The sale is live from `sale_start_ms` (inclusive) until `sale_end_ms`
+  /// (exclusive); a missing bound leaves that side open.
+  pub fn set_sale_window(
+    &mut self,
+    sale_start_ms: Option<u64>,
+    sale_end_ms: Option<u64>,
+  ) {
+    self.assert_owner();
+    if let (Some(start), Some(end)) = (sale_start_ms, sale_end_ms) {
+      assert!(start < end, "{}", Error::InvalidSaleWindow);
+    }
+    assert!(!self.deterministic_mint || sale_end_ms.is_some(), "{}", Error::SaleEndRequired);
 
-    Some(Token {
-      token_id,
-      owner_id,
-      metadata: Some(token_metadata),
-      approved_account_ids,
-    })
+    self.sale_start_ms = sale_start_ms;
+    self.sale_end_ms = sale_end_ms;
   }
 
-  pub fn get_owner(&self) -> AccountId {
-    self.tokens.owner_id.clone()
-  }
+  pub fn sale_status(&self) -> String {
+    let now = env::block_timestamp() / 1000000;
 
-  pub fn metadata_type_list(&self) -> Vec<String> {
-    let metadata_vector = self.metadata_per_type.keys_as_vector();
-    metadata_vector.to_vec()
+    if self.sale_start_ms.map(|start| now < start).unwrap_or(false) {
+      "not_started".to_string()
+    } else if self.sale_end_ms.map(|end| now >= end).unwrap_or(false) {
+      "ended".to_string()
+    } else {
+      "live".to_string()
+    }
   }
 
-  pub fn metadata_per_type_list(
-    &self,
-    metadata_type: MetadataType,
-  ) -> Vec<TokenMetadata> {
-    let metadata_vector = if let Some(metadata_set) = self.metadata_per_type.get(&metadata_type) {
-      metadata_set
-    } else {
-      return vec![];
-    };
+  /// A species is rolled with probability `weight / sum(weights)`; a zero
+  /// weight takes it out of public minting.
+  pub fn set_species_weights(
+    &mut self,
+    weights: Vec<u64>,
+  ) {
+    self.assert_owner();
+    assert_eq!(
+      weights.len() as u64,
+      SPECIES_COUNT,
+      "{}",
+      Error::WrongWeightCount,
+    );
+    assert!(
+      weights.iter().any(|weight| *weight > 0),
+      "{}",
+      Error::NoPositiveWeight,
+    );
+    // `roll_species` sums the weights, so the total has to fit in a u64.
+    weights
+      .iter()
+      .try_fold(0u64, |total, weight| total.checked_add(*weight))
+      .unwrap_or_else(|| panic!("{}", Error::WeightTotalOverflow));
 
-    metadata_vector.to_vec()
+    self.species_weights = weights;
   }
 
-  pub fn level_per_token(
-    &self, 
-    token_id: TokenId,
-  ) -> u64 {
-    self.level_per_token_id.get(&token_id).unwrap()
+  pub fn get_species_weights(&self) -> Vec<u64> {
+    self.species_weights.clone()
   }
 
-  fn increment_token_id(
-    &mut self,
-  ) {
-    let token_id_num: u64 = self.current_token_id.parse().unwrap(); 
-    let token_id_increment: u64 = &token_id_num + 1;
-    self.current_token_id = token_id_increment.to_string();
+  /// A species drawn with the live mint weights, for pre-mint previews.
+  /// It is seeded by the block, so repeated calls within one block agree,
+  /// and it says nothing about what the next real mint will roll.
+  pub fn preview_species(&self) -> u64 {
+    self.roll_species(&BlockRandomSource)
   }
 
-  fn get_metadata_per_type(
+  /// What `species` looks like at level `metadata_type`, rendered as the
+  /// next mint or evolve would. `issued_at`, `copies` and the cooldown in
+  /// `extra` are placeholders based on the current block; a real token gets
+  /// its own when it is created.
+  pub fn preview_metadata(
     &self,
     metadata_type: u64,
-    metadata_set: u64,
+    species: u64,
   ) -> TokenMetadata {
-    let current = env::block_timestamp() / 1000000;
-    let next = current + 300000;
-
-    let mut metadata = match metadata_type {
-      0 => metadatas::get_metadata_egg(metadata_set),
-      1 => metadatas::get_metadata_monster_1(metadata_set),
-      2 => metadatas::get_metadata_monster_2(metadata_set),
-      3 => metadatas::get_metadata_monster_3(metadata_set),
-      _ => panic!("You have reach the maximum level of your monster"),
-    };
+    assert!(species >= 1 && species <= SPECIES_COUNT, "{}", Error::InvalidSpecies);
+    let template = self.resolve_template(metadata_type, species)
+      .unwrap_or_else(|| panic!("{}", Error::UnknownLevel(metadata_type)));
 
-    metadata.issued_at = Some(current.to_string());
-    metadata.copies = Some(1u64);
-    metadata.extra = Some(next.to_string());
+    let copies = self.copies_per_template.get(&(metadata_type, species)).unwrap_or(0) + 1;
+    self.render_metadata(template, metadata_type, species, copies)
+  }
 
-    metadata
+  /// `None` lifts the cap. Counts are lifetime mints and ignore transfers.
+  pub fn set_max_mints_per_account(
+    &mut self,
+    max_mints_per_account: Option<u64>,
+  ) {
+    self.assert_owner();
+    self.max_mints_per_account = max_mints_per_account;
   }
 
-  fn get_random_number(&self) -> u8 {
-    let rand: u8 = *env::random_seed().get(0).unwrap();
-    rand
+  pub fn get_max_mints_per_account(&self) -> Option<u64> {
+    self.max_mints_per_account
   }
 
-}
+  pub fn mints_for_account(
+    &self,
+    account_id: AccountId,
+  ) -> u64 {
+    self.mints_per_account.get(&account_id).unwrap_or(0)
+  }
 
-#[near_bindgen]
-impl NonFungibleTokenMetadataProvider for Contract {
-    fn nft_metadata(&self) -> NFTContractMetadata {
-        self.metadata.get().unwrap()
+  #[payable]
+  pub fn nft_airdrop(
+    &mut self,
+    receiver_ids: Vec<AccountId>,
+    species: u64,
+  ) {
+    self.assert_owner();
+    assert!(species >= 1 && species <= SPECIES_COUNT, "{}", Error::InvalidSpecies);
+    assert_batch_size(receiver_ids.len(), MAX_BATCH);
+
+    let mint_count = receiver_ids.len() as u128;
+    let mut mint_logs = vec![];
+
+    for receiver_id in receiver_ids {
+      let token_id = self.internal_mint_egg_of_species(receiver_id.clone(), species);
+      mint_logs.push(NftMintLog {
+        owner_id: receiver_id,
+        token_ids: vec![token_id],
+        memo: None,
+      });
     }
-}
 
-fn refund_deposit() {
-  // let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
-  let required_cost = MINIMUM_EARLY_DEPOSIT;
+    events::emit(EventLogVariant::NftMint(mint_logs));
 
-  let attached_deposit = env::attached_deposit();
+    refund_deposit(MINIMUM_EARLY_DEPOSIT * mint_count);
+  }
 
-  assert!(
-    required_cost <= attached_deposit,
-    "Must attach 1 NEAR to cover storage",
-  );
+  /// Owner-only mint of a chosen species, e.g. for collab drops. Public
+  /// mints stay random through `nft_mint_egg`.
+  #[payable]
+  pub fn nft_mint_egg_species(
+    &mut self,
+    receiver_id: AccountId,
+    species: u64,
+  ) -> TokenId {
+    self.assert_owner();
+    assert!(species >= 1 && species <= SPECIES_COUNT, "{}", Error::InvalidSpecies);
 
-  let refund = attached_deposit - required_cost;
+    let token_id = self.internal_mint_egg_of_species(receiver_id.clone(), species);
+    events::emit(EventLogVariant::NftMint(vec![NftMintLog {
+      owner_id: receiver_id,
+      token_ids: vec![token_id.clone()],
+      memo: None,
+    }]));
 
-  if refund > 1 {
-    Promise::new(env::predecessor_account_id()).transfer(refund);
+    refund_deposit(MINIMUM_EARLY_DEPOSIT);
+    token_id
   }
-}
 
-#[cfg(all(test, not(target_arch = "wasm32")))]
-mod tests {
-    use super::*;
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
-    use near_sdk::MockedBlockchain;
-    use near_sdk::{testing_env};
+  #[payable]
+  pub fn nft_evolve(
+    &mut self,
+    token_id: TokenId,
+    msg: Option<String>,
+    branch: Option<u64>,
+    memo: Option<String>,
+  ) {
+    assert_memo_len(&memo);
+    let game_contract_id = msg.as_ref().map(|_| {
+      self.game_contract_id.clone().unwrap_or_else(|| panic!("{}", Error::GameContractNotConfigured))
+    });
+
+    let initial_storage_usage = env::storage_usage();
+    let owner_id = env::predecessor_account_id();
+    let new_token_id = self.internal_evolve(token_id.clone(), branch);
+
+    let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+    refund_deposit(self.evolve_fee + env::storage_byte_cost() * Balance::from(storage_used));
+
+    events::emit(EventLogVariant::NftBurn(vec![NftBurnLog {
+      owner_id: owner_id.clone(),
+      token_ids: vec![token_id.clone()],
+      memo: memo.clone(),
+    }]));
+    events::emit(EventLogVariant::NftMint(vec![NftMintLog {
+      owner_id: owner_id.clone(),
+      token_ids: vec![new_token_id.clone()],
+      memo,
+    }]));
+
+    // State is fully committed above, so the game contract only ever
+    // observes a finished evolution.
+    if let (Some(msg), Some(game_contract_id)) = (msg, game_contract_id) {
+      let level = self.level_per_token_id.get(&new_token_id).unwrap();
+      ext_game::on_nft_evolve(
+        owner_id.clone(),
+        token_id,
+        new_token_id.clone(),
+        level,
+        msg,
+        &game_contract_id,
+        NO_DEPOSIT,
+        GAS_FOR_GAME_ON_EVOLVE,
+      )
+      .then(ext_self::nft_resolve_evolve(
+        owner_id,
+        new_token_id,
+        &env::current_account_id(),
+        NO_DEPOSIT,
+        GAS_FOR_RESOLVE_EVOLVE,
+      ));
+    }
+  }
+
+  /// Caps how far `species` can evolve; `None` falls back to the levels
+  /// that have templates.
+  pub fn set_max_level_for_species(
+    &mut self,
+    species: u64,
+    max_level: Option<u64>,
+  ) {
+    self.assert_owner();
+    assert!(species >= 1 && species <= SPECIES_COUNT, "{}", Error::InvalidSpecies);
+    match max_level {
+      Some(max_level) => self.max_level_per_species.insert(&species, &max_level),
+      None => self.max_level_per_species.remove(&species),
+    };
+  }
+
+  pub fn get_max_level_for_species(
+    &self,
+    species: u64,
+  ) -> Option<u64> {
+    self.max_level_per_species.get(&species)
+  }
+
+  /// Hatches an egg into its level 1 form once its cooldown is over; the
+  /// hatchling stays with the caller. Same deposit as `nft_evolve`.
+  #[payable]
+  pub fn claim_hatch(
+    &mut self,
+    token_id: TokenId,
+  ) -> TokenId {
+    self.internal_live_token_owner(&token_id);
+    let level = self.level_per_token_id.get(&token_id).unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+    assert_eq!(level, 0, "{}", Error::AlreadyHatched);
+    let ready_at = self.tokens.token_metadata_by_id
+      .as_ref()
+      .and_then(|by_id| by_id.get(&token_id))
+      .and_then(|metadata| metadata.extra)
+      .map(|extra| TokenExtra::parse(&extra).evolve_at)
+      .unwrap_or_else(|| panic!("{}", Error::TokenHasNoMetadata));
+    assert!(env::block_timestamp() / 1000000 >= ready_at, "{}", Error::EggNotReady(ready_at));
+
+    self.nft_evolve(token_id, None, None, Some("hatched".to_string()));
+    self.current_token_id.to_string()
+  }
+
+  /// Evolves through every stage up to `target_level` in one call. Each
+  /// stage's cooldown runs from the moment the previous stage became ready,
+  /// so a jump of `n` stages needs `n - 1` extra cooldowns to have elapsed.
+  #[payable]
+  pub fn nft_evolve_to(
+    &mut self,
+    token_id: TokenId,
+    target_level: u64,
+  ) -> TokenId {
+    let level = self.level_per_token_id.get(&token_id).unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+    assert!(target_level > level, "{}", Error::TargetLevelTooLow);
+    let species = self.egg_per_token_id.get(&token_id).unwrap();
+    assert!(self.has_template_for_level(target_level, species), "{}", Error::MaxLevelReached);
+
+    let hops = target_level - level;
+    let mut evolve_at = self.tokens.token_metadata_by_id
+      .as_ref()
+      .and_then(|by_id| by_id.get(&token_id))
+      .and_then(|metadata| metadata.extra)
+      .map(|extra| TokenExtra::parse(&extra).evolve_at)
+      .unwrap_or_else(|| panic!("{}", Error::TokenHasNoMetadata));
+    let now = env::block_timestamp() / 1000000;
+    let mut ready_at = evolve_at;
+    for stage in 0..hops {
+      if stage > 0 {
+        ready_at = ready_at.saturating_add(self.cooldown_for_level(level + stage).0);
+      }
+      assert!(
+        now >= ready_at,
+        "{}",
+        Error::StageNotReady(level + stage + 1, ready_at),
+      );
+    }
+
+    let initial_storage_usage = env::storage_usage();
+    let owner_id = env::predecessor_account_id();
+    let mut burned_ids = vec![];
+    let mut current_id = token_id;
+
+    for stage in 0..hops {
+      let new_token_id = self.internal_evolve(current_id.clone(), None);
+      burned_ids.push(current_id);
+      current_id = new_token_id;
+
+      if stage + 1 < hops {
+        evolve_at = evolve_at.saturating_add(self.cooldown_for_level(level + stage + 1).0);
+        self.internal_set_evolve_at(&current_id, evolve_at);
+      }
+    }
+
+    let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+    refund_deposit(
+      self.evolve_fee * Balance::from(hops) + env::storage_byte_cost() * Balance::from(storage_used),
+    );
+
+    let memo = Some(format!("evolved to level {}", target_level));
+    events::emit(EventLogVariant::NftBurn(vec![NftBurnLog {
+      owner_id: owner_id.clone(),
+      token_ids: burned_ids,
+      memo: memo.clone(),
+    }]));
+    events::emit(EventLogVariant::NftMint(vec![NftMintLog {
+      owner_id,
+      token_ids: vec![current_id.clone()],
+      memo,
+    }]));
+
+    current_id
+  }
+
+  /// Evolves `token_id` for the caller and hands the evolved token straight
+  /// to `recipient`, so a gift never sits in the caller's account.
+  #[payable]
+  pub fn evolve_and_transfer(
+    &mut self,
+    token_id: TokenId,
+    recipient: ValidAccountId,
+  ) -> TokenId {
+    let initial_storage_usage = env::storage_usage();
+    let owner_id = env::predecessor_account_id();
+    let new_token_id = self.internal_evolve(token_id.clone(), None);
+    let level = self.level_per_token_id.get(&new_token_id).unwrap();
+
+    let memo = Some(format!("evolved to level {}", level));
+    events::emit(EventLogVariant::NftBurn(vec![NftBurnLog {
+      owner_id: owner_id.clone(),
+      token_ids: vec![token_id],
+      memo: memo.clone(),
+    }]));
+    events::emit(EventLogVariant::NftMint(vec![NftMintLog {
+      owner_id: owner_id.clone(),
+      token_ids: vec![new_token_id.clone()],
+      memo,
+    }]));
+
+    self.internal_transfer(&owner_id, recipient.as_ref(), &new_token_id, None, None);
+
+    let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+    refund_deposit(self.evolve_fee + env::storage_byte_cost() * Balance::from(storage_used));
+
+    new_token_id
+  }
+
+  #[private]
+  pub fn nft_resolve_evolve(
+    &mut self,
+    owner_id: AccountId,
+    token_id: TokenId,
+  ) -> bool {
+    match env::promise_result(0) {
+      PromiseResult::Successful(_) => true,
+      _ => {
+        env::log(format!("Game contract was not notified of evolve {} by {}", token_id, owner_id).as_bytes());
+        false
+      }
+    }
+  }
+
+  pub fn set_owner_id(
+    &mut self,
+    new_owner_id: ValidAccountId,
+  ) {
+    self.assert_owner();
+    let new_owner_id: AccountId = new_owner_id.into();
+    let previous_owner_id = self.owner_id.clone();
+    assert_ne!(previous_owner_id, new_owner_id, "{}", Error::AlreadyOwner);
+
+    let changed_at = env::block_timestamp() / 1000000;
+    self.owner_history.insert(&self.owner_history.len(), &(previous_owner_id.clone(), changed_at));
+    self.owner_id = new_owner_id.clone();
+    self.tokens.owner_id = new_owner_id.clone();
+
+    env::log(format!("Owner changed from {} to {} at {}", previous_owner_id, new_owner_id, changed_at).as_bytes());
+  }
+
+  /// Previous owners, oldest first, with the time each one handed over.
+  pub fn owner_history(&self) -> Vec<(AccountId, U64)> {
+    self.owner_history
+      .values()
+      .map(|(account_id, changed_at)| (account_id, U64(changed_at)))
+      .collect()
+  }
+
+  /// Registers the forms `species` can take at `level`; `nft_evolve` picks
+  /// one with its `branch` index. An empty list removes the branching.
+  pub fn set_evolve_branches(
+    &mut self,
+    species: u64,
+    level: u64,
+    forms: Vec<TokenMetadata>,
+  ) {
+    self.assert_owner();
+    self.assert_metadata_not_frozen();
+    assert!(species >= 1 && species <= SPECIES_COUNT, "{}", Error::InvalidSpecies);
+    assert!(level >= 1, "{}", Error::TargetLevelTooLow);
+
+    if forms.is_empty() {
+      self.evolve_branches.remove(&(species, level));
+    } else {
+      self.evolve_branches.insert(&(species, level), &forms);
+    }
+  }
+
+  pub fn get_evolve_branches(
+    &self,
+    species: u64,
+    level: u64,
+  ) -> Vec<TokenMetadata> {
+    self.evolve_branches.get(&(species, level)).unwrap_or_default()
+  }
+
+  pub fn set_game_contract(
+    &mut self,
+    game_contract_id: Option<ValidAccountId>,
+  ) {
+    self.assert_owner();
+    self.game_contract_id = game_contract_id.map(|account_id| account_id.into());
+  }
+
+  /// `treasury_bps` of every public mint fee, after the storage the mint
+  /// locked, goes to `treasury`; `None` keeps it all in the contract.
+  pub fn set_treasury(
+    &mut self,
+    treasury: Option<ValidAccountId>,
+    treasury_bps: u16,
+  ) {
+    self.assert_owner();
+    assert!(treasury_bps <= MAX_BPS, "{}", Error::InvalidBps);
+    self.treasury = treasury.map(|account_id| account_id.into());
+    self.treasury_bps = treasury_bps;
+  }
+
+  pub fn get_treasury(&self) -> Option<(AccountId, u16)> {
+    self.treasury.clone().map(|treasury| (treasury, self.treasury_bps))
+  }
+
+  /// Evolving into `level` consumes `amount` of the fungible token at
+  /// `item_contract`, deposited beforehand through `ft_transfer_call`.
+  /// Unspent deposits can be taken back with `withdraw_item`.
+  pub fn set_required_item(
+    &mut self,
+    level: u64,
+    required_item: Option<(ValidAccountId, U128)>,
+  ) {
+    self.assert_owner();
+    match required_item {
+      Some((item_contract, amount)) => {
+        assert!(amount.0 > 0, "{}", Error::ZeroAmount);
+        self.required_item_per_level.insert(&level, &(item_contract.into(), amount.0));
+      }
+      None => {
+        self.required_item_per_level.remove(&level);
+      }
+    }
+  }
+
+  pub fn get_required_item(
+    &self,
+    level: u64,
+  ) -> Option<(AccountId, U128)> {
+    self.required_item_per_level
+      .get(&level)
+      .map(|(item_contract, amount)| (item_contract, U128(amount)))
+  }
+
+  pub fn item_balance(
+    &self,
+    account_id: AccountId,
+    item_contract: AccountId,
+  ) -> U128 {
+    U128(self.item_balances.get(&(account_id, item_contract)).unwrap_or(0))
+  }
+
+  /// NEP-141 receiver. Items sent with msg `evolve` from a contract that
+  /// gates some level are credited to `sender_id`; anything else is
+  /// returned in full.
+  pub fn ft_on_transfer(
+    &mut self,
+    sender_id: ValidAccountId,
+    amount: U128,
+    msg: String,
+  ) -> PromiseOrValue<U128> {
+    let item_contract = env::predecessor_account_id();
+    let is_item = self.required_item_per_level
+      .values()
+      .any(|(required_contract, _)| required_contract == item_contract);
+    if msg != EVOLVE_ITEM_MSG || !is_item {
+      return PromiseOrValue::Value(amount);
+    }
+
+    let key: (AccountId, AccountId) = (sender_id.into(), item_contract);
+    let balance = self.item_balances.get(&key).unwrap_or(0);
+    self.item_balances.insert(&key, &(balance + amount.0));
+    PromiseOrValue::Value(U128(0))
+  }
+
+  /// Sends `amount` (the whole balance when `None`) of the caller's
+  /// deposited items back through `ft_transfer`. The caller must be
+  /// registered with `item_contract`; a failed transfer is credited back.
+  #[payable]
+  pub fn withdraw_item(
+    &mut self,
+    item_contract: ValidAccountId,
+    amount: Option<U128>,
+  ) -> Promise {
+    assert_one_yocto();
+    let account_id = env::predecessor_account_id();
+    let item_contract: AccountId = item_contract.into();
+    let balance = self.item_balances.get(&(account_id.clone(), item_contract.clone())).unwrap_or(0);
+    let amount = amount.map_or(balance, |amount| amount.0);
+    assert!(amount > 0, "{}", Error::ZeroAmount);
+    assert!(amount <= balance, "{}", Error::ItemBalanceTooLow(balance));
+
+    self.internal_set_item_balance(&account_id, &item_contract, balance - amount);
+
+    ext_fungible_token::ft_transfer(
+      account_id.clone(),
+      U128(amount),
+      None,
+      &item_contract,
+      1,
+      GAS_FOR_FT_TRANSFER,
+    )
+    .then(ext_self::resolve_item_withdraw(
+      account_id,
+      item_contract,
+      U128(amount),
+      &env::current_account_id(),
+      NO_DEPOSIT,
+      GAS_FOR_RESOLVE_ITEM_WITHDRAW,
+    ))
+  }
+
+  /// Callback of `withdraw_item`; credits `amount` back when the transfer
+  /// failed.
+  #[private]
+  pub fn resolve_item_withdraw(
+    &mut self,
+    account_id: AccountId,
+    item_contract: AccountId,
+    amount: U128,
+  ) -> bool {
+    match env::promise_result(0) {
+      PromiseResult::Successful(_) => true,
+      _ => {
+        let balance = self.item_balances.get(&(account_id.clone(), item_contract.clone())).unwrap_or(0);
+        self.internal_set_item_balance(&account_id, &item_contract, balance + amount.0);
+        false
+      }
+    }
+  }
+
+  /// Contract told about every `nft_mint_egg` through `on_nft_minted`;
+  /// `None` stops the notifications.
+  pub fn set_registry_contract(
+    &mut self,
+    registry_contract: Option<ValidAccountId>,
+  ) {
+    self.assert_owner();
+    self.registry_contract = registry_contract.map(|account_id| account_id.into());
+  }
+
+  pub fn set_base_uri(
+    &mut self,
+    base_uri: String,
+  ) {
+    self.assert_owner();
+    assert!(is_valid_uri(&base_uri), "{}", Error::InvalidBaseUri);
+
+    let memo = format!("base_uri: {}", base_uri);
+    self.update_contract_metadata(memo, |metadata| metadata.base_uri = Some(base_uri));
+  }
+
+  /// When set, newly minted and evolved tokens get their `media` from the
+  /// renderer as `{renderer_base}/{species}/{level}`.
+  pub fn set_renderer_base(
+    &mut self,
+    renderer_base: Option<String>,
+  ) {
+    self.assert_owner();
+    self.assert_metadata_not_frozen();
+    if let Some(renderer_base) = &renderer_base {
+      assert!(is_valid_uri(renderer_base), "{}", Error::InvalidBaseUri);
+    }
+
+    self.renderer_base = renderer_base.map(|base| base.trim_end_matches('/').to_string());
+  }
+
+  pub fn get_renderer_base(&self) -> Option<String> {
+    self.renderer_base.clone()
+  }
+
+  /// Fallback `media` for newly minted and evolved tokens whose template
+  /// has none, so every token stays renderable.
+  pub fn set_default_metadata_icon(
+    &mut self,
+    default_media: Option<String>,
+  ) {
+    self.assert_owner();
+    self.assert_metadata_not_frozen();
+    if let Some(default_media) = &default_media {
+      assert!(is_valid_uri(default_media), "{}", Error::InvalidBaseUri);
+    }
+
+    self.default_media = default_media;
+  }
+
+  /// When on, eggs only mint or reroll into species with a registered `egg` template.
+  pub fn set_templates_required(
+    &mut self,
+    templates_required: bool,
+  ) {
+    self.assert_owner();
+    self.templates_required = templates_required;
+  }
+
+  /// One-way: templates, evolve branches, the renderer and the contract
+  /// metadata can never change again.
+  pub fn freeze_metadata(&mut self) {
+    self.assert_owner();
+    self.metadata_frozen = true;
+  }
+
+  pub fn is_metadata_frozen(&self) -> bool {
+    self.metadata_frozen
+  }
+
+  pub fn set_contract_name(
+    &mut self,
+    name: String,
+  ) {
+    self.assert_owner();
+    assert!(!name.trim().is_empty(), "{}", Error::EmptyName);
+
+    let memo = format!("name: {}", name);
+    self.update_contract_metadata(memo, |metadata| metadata.name = name);
+  }
+
+  pub fn set_contract_symbol(
+    &mut self,
+    symbol: String,
+  ) {
+    self.assert_owner();
+    assert!(!symbol.trim().is_empty(), "{}", Error::EmptySymbol);
+
+    let memo = format!("symbol: {}", symbol);
+    self.update_contract_metadata(memo, |metadata| metadata.symbol = symbol);
+  }
+
+  pub fn set_contract_icon(
+    &mut self,
+    icon: Option<String>,
+  ) {
+    self.assert_owner();
+
+    self.update_contract_metadata("icon".to_string(), |metadata| metadata.icon = icon);
+  }
+
+  /// Scales the evolve cooldown with level: `base_cooldown_ms` at level 0,
+  /// multiplied by `cooldown_multiplier_bps / 10000` per level. `None`
+  /// restores the flat default.
+  pub fn set_cooldown_formula(
+    &mut self,
+    base_cooldown_ms: Option<u64>,
+    cooldown_multiplier_bps: u32,
+  ) {
+    self.assert_owner();
+    self.base_cooldown_ms = base_cooldown_ms;
+    self.cooldown_multiplier_bps = cooldown_multiplier_bps;
+  }
+
+  /// Milliseconds a token has to wait after reaching `level`.
+  pub fn cooldown_for_level(
+    &self,
+    level: u64,
+  ) -> U64 {
+    let base = match self.base_cooldown_ms {
+      Some(base) => base,
+      None => return U64(EVOLVE_COOLDOWN_MS),
+    };
+
+    let mut cooldown = u128::from(base);
+    for _ in 0..level {
+      if cooldown >= u128::from(u64::MAX) || cooldown == 0 {
+        break;
+      }
+      cooldown = cooldown * u128::from(self.cooldown_multiplier_bps) / u128::from(MAX_BPS);
+    }
+    U64(cooldown.min(u128::from(u64::MAX)) as u64)
+  }
+
+  /// Flat fee charged on top of the storage an evolve uses; zero makes
+  /// evolving free apart from storage.
+  pub fn set_evolve_fee(
+    &mut self,
+    evolve_fee: U128,
+  ) {
+    self.assert_owner();
+    self.evolve_fee = evolve_fee.into();
+  }
+
+  pub fn get_evolve_fee(&self) -> U128 {
+    U128(self.evolve_fee)
+  }
+
+  /// Re-rolls the species of an unhatched egg for `reroll_fee` plus any
+  /// storage the new metadata needs. Returns the new species.
+  #[payable]
+  pub fn reroll_egg(
+    &mut self,
+    token_id: TokenId,
+  ) -> u64 {
+    let initial_storage_usage = env::storage_usage();
+    let species = self.internal_reroll_egg(&token_id, &BlockRandomSource);
+
+    let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+    refund_deposit(self.reroll_fee + env::storage_byte_cost() * Balance::from(storage_used));
+
+    species
+  }
+
+  pub fn set_reroll_fee(
+    &mut self,
+    reroll_fee: U128,
+  ) {
+    self.assert_owner();
+    self.reroll_fee = reroll_fee.into();
+  }
+
+  pub fn get_reroll_fee(&self) -> U128 {
+    U128(self.reroll_fee)
+  }
+
+  /// Support tool: makes `token_id` evolvable right away, e.g. after a bug
+  /// wrongly pushed its cooldown out.
+  pub fn admin_reset_evolve_timer(
+    &mut self,
+    token_id: TokenId,
+  ) {
+    self.assert_owner();
+    assert!(self.tokens.owner_by_id.get(&token_id).is_some(), "{}", Error::TokenNotFound);
+
+    self.internal_set_evolve_at(&token_id, env::block_timestamp() / 1000000);
+
+    events::emit(EventLogVariant::NftMetadataUpdate(vec![NftMetadataUpdateLog {
+      token_ids: vec![token_id],
+      memo: Some("evolve timer reset".to_string()),
+    }]));
+  }
+
+  /// Rebuilds a token's metadata from the current template for its species
+  /// and level, or the branch form it evolved into, keeping `issued_at`,
+  /// `copies` and the evolve timer.
+  pub fn regenerate_metadata(
+    &mut self,
+    token_id: TokenId,
+  ) {
+    self.assert_owner();
+    assert!(self.tokens.owner_by_id.get(&token_id).is_some(), "{}", Error::TokenNotFound);
+
+    self.internal_regenerate_metadata(&token_id);
+
+    events::emit(EventLogVariant::NftMetadataUpdate(vec![NftMetadataUpdateLog {
+      token_ids: vec![token_id],
+      memo: Some("metadata regenerated".to_string()),
+    }]));
+  }
+
+  /// `regenerate_metadata` for one page of tokens, capped at `MAX_PAGE_LIMIT`.
+  /// Pages follow `owner_by_id`'s string key order ("10" sorts before "2"),
+  /// which stays stable as long as nothing is minted or burned in between.
+  pub fn regenerate_metadata_range(
+    &mut self,
+    from_index: Option<U128>,
+    limit: Option<u64>,
+  ) {
+    self.assert_owner();
+    let start = u128::from(from_index.unwrap_or(U128(0)));
+    let limit = limit.unwrap_or(MAX_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+
+    let token_ids: Vec<TokenId> = self.tokens.owner_by_id
+      .iter()
+      .skip(start as usize)
+      .take(limit as usize)
+      .map(|(token_id, _)| token_id)
+      .collect();
+    if token_ids.is_empty() {
+      return;
+    }
+
+    for token_id in &token_ids {
+      self.internal_regenerate_metadata(token_id);
+    }
+
+    events::emit(EventLogVariant::NftMetadataUpdate(vec![NftMetadataUpdateLog {
+      token_ids,
+      memo: Some("metadata regenerated".to_string()),
+    }]));
+  }
+
+  /// Storage added by a transfer (e.g. a new per-owner set for the receiver)
+  /// is covered by the contract, so only the one yoctoNEAR guard is required.
+  /// Any valid account may receive, contracts included, and nothing is
+  /// called on the receiver; use `nft_transfer_call` for that.
+  #[payable]
+  pub fn nft_transfer(
+    &mut self,
+    receiver_id: ValidAccountId,
+    token_id: TokenId,
+    approval_id: Option<u64>,
+    memo: Option<String>,
+  ) {
+    assert_one_yocto();
+    let sender_id = env::predecessor_account_id();
+    self.internal_transfer(&sender_id, receiver_id.as_ref(), &token_id, approval_id, memo);
+  }
+
+  #[payable]
+  pub fn nft_transfer_call(
+    &mut self,
+    receiver_id: ValidAccountId,
+    token_id: TokenId,
+    approval_id: Option<u64>,
+    memo: Option<String>,
+    msg: String,
+  ) -> PromiseOrValue<bool> {
+    assert_one_yocto();
+    let sender_id = env::predecessor_account_id();
+    let (previous_owner_id, previous_approvals) =
+      self.internal_transfer(&sender_id, receiver_id.as_ref(), &token_id, approval_id, memo);
+
+    ext_non_fungible_token_receiver::nft_on_transfer(
+      sender_id,
+      previous_owner_id.clone(),
+      token_id.clone(),
+      msg,
+      receiver_id.as_ref(),
+      NO_DEPOSIT,
+      env::prepaid_gas() - GAS_FOR_NFT_TRANSFER_CALL,
+    )
+    .then(ext_self::nft_resolve_transfer(
+      previous_owner_id,
+      receiver_id.into(),
+      token_id,
+      previous_approvals,
+      &env::current_account_id(),
+      NO_DEPOSIT,
+      GAS_FOR_RESOLVE_TRANSFER,
+    ))
+    .into()
+  }
+
+  /// Callback of `nft_transfer_call`. The token goes back to
+  /// `previous_owner_id` when the receiver returned `true`, returned
+  /// something unparsable or failed, and stays put on `false`. Species,
+  /// level and lineage are keyed by token id, so only ownership moves.
+  #[private]
+  pub fn nft_resolve_transfer(
+    &mut self,
+    previous_owner_id: AccountId,
+    receiver_id: AccountId,
+    token_id: TokenId,
+    approved_account_ids: Option<HashMap<AccountId, u64>>,
+  ) -> bool {
+    // A sender who sent their last token has no set left; the standard would
+    // recreate it under its own key on revert, so create it here instead.
+    if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
+      if !tokens_per_owner.contains_key(&previous_owner_id) {
+        tokens_per_owner.insert(&previous_owner_id, &UnorderedSet::new(tokens_per_owner_key(&previous_owner_id)));
+      }
+    }
+
+    let transferred = self.tokens.nft_resolve_transfer(
+      previous_owner_id.clone(),
+      receiver_id.clone(),
+      token_id.clone(),
+      approved_account_ids,
+    );
+
+    if transferred {
+      if let Some(token_set) = self.internal_owner_token_set(&previous_owner_id) {
+        self.internal_set_owner_token_set(&previous_owner_id, &token_set);
+      }
+    } else {
+      self.internal_remove_token_from_owner_count(&receiver_id);
+      self.internal_add_token_to_owner_count(&previous_owner_id);
+
+      events::emit(EventLogVariant::NftTransfer(vec![NftTransferLog {
+        authorized_id: None,
+        old_owner_id: receiver_id,
+        new_owner_id: previous_owner_id,
+        token_ids: vec![token_id],
+        memo: None,
+      }]));
+    }
+
+    transferred
+  }
+
+  #[payable]
+  pub fn nft_burn(
+    &mut self,
+    token_id: TokenId,
+  ) {
+    assert_one_yocto();
+    let initial_storage_usage = env::storage_usage();
+
+    let owner_id = self.tokens.owner_by_id.get(&token_id).unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+    assert_eq!(
+      owner_id,
+      env::predecessor_account_id(),
+      "{}",
+      Error::NotTokenOwner,
+    );
+
+    self.internal_burn(&token_id, &owner_id);
+
+    events::emit(EventLogVariant::NftBurn(vec![NftBurnLog {
+      owner_id: owner_id.clone(),
+      token_ids: vec![token_id],
+      memo: None,
+    }]));
+
+    refund_storage(owner_id, initial_storage_usage.saturating_sub(env::storage_usage()));
+  }
+
+  /// Burns the caller's token and pays out the redemption set for its level
+  /// from the redemption pool, along with the storage the burn frees.
+  #[payable]
+  pub fn burn_to_redeem(
+    &mut self,
+    token_id: TokenId,
+  ) -> U128 {
+    assert_one_yocto();
+    let initial_storage_usage = env::storage_usage();
+
+    let owner_id = self.tokens.owner_by_id.get(&token_id).unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+    assert_eq!(owner_id, env::predecessor_account_id(), "{}", Error::NotTokenOwner);
+
+    let level = self.level_per_token_id.get(&token_id).unwrap_or(0);
+    let redemption = self.redemption_per_level.get(&level).unwrap_or(0);
+    assert!(
+      redemption <= self.redemption_pool,
+      "{}",
+      Error::RedemptionPoolUnderfunded(self.redemption_pool),
+    );
+
+    self.internal_burn(&token_id, &owner_id);
+    self.redemption_pool -= redemption;
+
+    events::emit(EventLogVariant::NftBurn(vec![NftBurnLog {
+      owner_id: owner_id.clone(),
+      token_ids: vec![token_id],
+      memo: Some(format!("redeemed for {}", redemption)),
+    }]));
+
+    let storage_freed = initial_storage_usage.saturating_sub(env::storage_usage());
+    let payout = redemption + env::storage_byte_cost() * Balance::from(storage_freed);
+    if payout > 0 {
+      Promise::new(owner_id).transfer(payout);
+    }
+    U128(redemption)
+  }
+
+  /// Burns every token in `token_ids` or none of them: ownership and locks
+  /// are checked for the whole batch before the first token goes.
+  #[payable]
+  pub fn nft_burn_many(
+    &mut self,
+    token_ids: Vec<TokenId>,
+  ) {
+    assert_one_yocto();
+    assert_batch_size(token_ids.len(), MAX_BATCH);
+    let initial_storage_usage = env::storage_usage();
+
+    let mut unique_ids = token_ids.clone();
+    unique_ids.sort();
+    unique_ids.dedup();
+    assert_eq!(unique_ids.len(), token_ids.len(), "{}", Error::DuplicateTokenId);
+
+    let owner_id = env::predecessor_account_id();
+    for token_id in &token_ids {
+      let token_owner_id = self.tokens.owner_by_id
+        .get(token_id)
+        .unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+      assert_eq!(token_owner_id, owner_id, "{}", Error::NotTokenOwner);
+      self.assert_not_locked(token_id);
+    }
+
+    for token_id in &token_ids {
+      self.internal_burn(token_id, &owner_id);
+    }
+
+    events::emit(EventLogVariant::NftBurn(vec![NftBurnLog {
+      owner_id: owner_id.clone(),
+      token_ids,
+      memo: None,
+    }]));
+
+    refund_storage(owner_id, initial_storage_usage.saturating_sub(env::storage_usage()));
+  }
+
+  #[payable]
+  pub fn nft_approve(
+    &mut self,
+    token_id: TokenId,
+    account_id: ValidAccountId,
+    msg: Option<String>,
+  ) -> Option<Promise> {
+    self.nft_approve_many(vec![token_id], account_id, msg);
+    None
+  }
+
+  /// Approves `account_id` on every token in one call. The caller must own
+  /// all of them and attach enough to cover the new approvals' storage.
+  #[payable]
+  pub fn nft_approve_many(
+    &mut self,
+    token_ids: Vec<TokenId>,
+    account_id: ValidAccountId,
+    msg: Option<String>,
+  ) -> Vec<U64> {
+    self.internal_approve_many(token_ids, account_id.into(), None, msg)
+      .into_iter()
+      .map(U64)
+      .collect()
+  }
+
+  /// Like `nft_approve`, but the approval lapses at `expires_at` (ms), after
+  /// which it is treated as revoked.
+  #[payable]
+  pub fn nft_approve_until(
+    &mut self,
+    token_id: TokenId,
+    account_id: ValidAccountId,
+    expires_at: U64,
+    msg: Option<String>,
+  ) -> Option<Promise> {
+    assert!(expires_at.0 > env::block_timestamp() / 1000000, "{}", Error::InvalidExpiry);
+    self.internal_approve_many(vec![token_id], account_id.into(), Some(expires_at.0), msg);
+    None
+  }
+
+  #[payable]
+  pub fn nft_revoke(
+    &mut self,
+    token_id: TokenId,
+    account_id: ValidAccountId,
+  ) {
+    assert_one_yocto();
+    let initial_storage_usage = env::storage_usage();
+
+    let owner_id = self.tokens.owner_by_id
+      .get(&token_id)
+      .unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+    assert_eq!(owner_id, env::predecessor_account_id(), "{}", Error::NotTokenOwner);
+
+    let approvals_by_id = self.tokens.approvals_by_id
+      .as_mut()
+      .unwrap_or_else(|| panic!("{}", Error::ApprovalsDisabled));
+    let mut revoked = false;
+    if let Some(mut approvals) = approvals_by_id.get(&token_id) {
+      if approvals.remove(account_id.as_ref()).is_some() {
+        revoked = true;
+        if approvals.is_empty() {
+          approvals_by_id.remove(&token_id);
+        } else {
+          approvals_by_id.insert(&token_id, &approvals);
+        }
+      }
+    }
+    if let Some(mut expiries) = self.approval_expiry_by_id.get(&token_id) {
+      if expiries.remove(account_id.as_ref()).is_some() {
+        self.internal_set_approval_expiries(&token_id, expiries);
+      }
+    }
+
+    if revoked {
+      events::emit_nearmon(NearmonEventVariant::ApprovalRevoked(vec![ApprovalRevokedLog {
+        owner_id: owner_id.clone(),
+        token_id,
+        account_ids: vec![account_id.into()],
+      }]));
+    }
+
+    refund_storage(owner_id, initial_storage_usage.saturating_sub(env::storage_usage()));
+  }
+
+  /// Drops every approval on `token_id` and restarts its approval ids; a
+  /// single `nft_revoke` leaves the counter alone.
+  #[payable]
+  pub fn nft_revoke_all(
+    &mut self,
+    token_id: TokenId,
+  ) {
+    assert_one_yocto();
+    let initial_storage_usage = env::storage_usage();
+
+    let owner_id = self.tokens.owner_by_id
+      .get(&token_id)
+      .unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+    assert_eq!(owner_id, env::predecessor_account_id(), "{}", Error::NotTokenOwner);
+
+    let approvals = self.tokens.approvals_by_id
+      .as_mut()
+      .unwrap_or_else(|| panic!("{}", Error::ApprovalsDisabled))
+      .remove(&token_id)
+      .unwrap_or_default();
+    if let Some(next_approval_id_by_id) = &mut self.tokens.next_approval_id_by_id {
+      next_approval_id_by_id.remove(&token_id);
+    }
+    self.approval_expiry_by_id.remove(&token_id);
+
+    if !approvals.is_empty() {
+      let mut account_ids: Vec<AccountId> = approvals.into_iter().map(|(account_id, _)| account_id).collect();
+      account_ids.sort();
+      events::emit_nearmon(NearmonEventVariant::ApprovalRevoked(vec![ApprovalRevokedLog {
+        owner_id: owner_id.clone(),
+        token_id,
+        account_ids,
+      }]));
+    }
+
+    refund_storage(owner_id, initial_storage_usage.saturating_sub(env::storage_usage()));
+  }
+
+  /// Lists accounts that co-own `token_id` off-chain, for display only. An
+  /// empty list clears it; new storage is paid from the attached deposit.
+  #[payable]
+  pub fn set_co_owners(
+    &mut self,
+    token_id: TokenId,
+    co_owners: Vec<ValidAccountId>,
+  ) {
+    assert!(env::attached_deposit() > 0, "{}", Error::DepositRequired);
+    assert!(co_owners.len() <= MAX_CO_OWNERS, "{}", Error::TooManyCoOwners(MAX_CO_OWNERS));
+    let initial_storage_usage = env::storage_usage();
+
+    let owner_id = self.tokens.owner_by_id
+      .get(&token_id)
+      .unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+    assert_eq!(owner_id, env::predecessor_account_id(), "{}", Error::NotTokenOwner);
+
+    if co_owners.is_empty() {
+      self.co_owners_per_token.remove(&token_id);
+    } else {
+      let co_owners: Vec<AccountId> = co_owners.into_iter().map(|account_id| account_id.into()).collect();
+      self.co_owners_per_token.insert(&token_id, &co_owners);
+    }
+
+    let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+    refund_deposit(env::storage_byte_cost() * Balance::from(storage_used));
+    refund_storage(owner_id, initial_storage_usage.saturating_sub(env::storage_usage()));
+  }
+
+  pub fn co_owners(
+    &self,
+    token_id: TokenId,
+  ) -> Vec<AccountId> {
+    self.co_owners_per_token.get(&token_id).unwrap_or_default()
+  }
+
+  /// Names the token; an empty name clears it. The nickname stays with the
+  /// token when it is transferred and carries over to the evolved id, and
+  /// new storage is paid from the attached deposit.
+  #[payable]
+  pub fn set_nickname(
+    &mut self,
+    token_id: TokenId,
+    name: String,
+  ) {
+    assert!(env::attached_deposit() > 0, "{}", Error::DepositRequired);
+    let name = name.trim();
+    assert!(
+      name.chars().count() <= MAX_NICKNAME_LEN && !name.chars().any(char::is_control),
+      "{}",
+      Error::InvalidNickname(MAX_NICKNAME_LEN),
+    );
+    let initial_storage_usage = env::storage_usage();
+
+    let owner_id = self.tokens.owner_by_id
+      .get(&token_id)
+      .unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+    assert_eq!(owner_id, env::predecessor_account_id(), "{}", Error::NotTokenOwner);
+
+    if name.is_empty() {
+      self.nickname_per_token.remove(&token_id);
+    } else {
+      self.nickname_per_token.insert(&token_id, &name.to_string());
+    }
+
+    let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+    refund_deposit(env::storage_byte_cost() * Balance::from(storage_used));
+    refund_storage(owner_id, initial_storage_usage.saturating_sub(env::storage_usage()));
+  }
+
+  pub fn nickname(
+    &self,
+    token_id: TokenId,
+  ) -> Option<String> {
+    self.nickname_per_token.get(&token_id)
+  }
+
+  /// NEP-178: whether `approved_account_id` is approved for `token_id`, and
+  /// when `approval_id` is given, whether it is the current approval id.
+  pub fn nft_is_approved(
+    &self,
+    token_id: TokenId,
+    approved_account_id: ValidAccountId,
+    approval_id: Option<u64>,
+  ) -> bool {
+    let stored_approval_id = self.tokens
+      .approvals_by_id
+      .as_ref()
+      .and_then(|by_id| by_id.get(&token_id))
+      .and_then(|approvals| approvals.get(approved_account_id.as_ref()).copied());
+
+    match (stored_approval_id, approval_id) {
+      (Some(stored), _) if self.is_approval_expired(&token_id, approved_account_id.as_ref(), stored) => false,
+      (Some(stored), Some(expected)) => stored == expected,
+      (Some(_), None) => true,
+      (None, _) => false,
+    }
+  }
+
+  /// Approved accounts with their approval ids, empty when there are none.
+  pub fn approved_accounts(
+    &self,
+    token_id: TokenId,
+  ) -> Vec<(AccountId, U64)> {
+    self.tokens
+      .approvals_by_id
+      .as_ref()
+      .and_then(|by_id| by_id.get(&token_id))
+      .map(|approvals| {
+        let mut approvals: Vec<(AccountId, U64)> = approvals
+          .into_iter()
+          .map(|(account_id, approval_id)| (account_id, U64(approval_id)))
+          .collect();
+        approvals.sort_by_key(|(_, approval_id)| approval_id.0);
+        approvals
+      })
+      .unwrap_or_default()
+  }
+
+  /// Adds the attached deposit to the pool `burn_to_redeem` pays from.
+  #[payable]
+  pub fn fund_redemption_pool(&mut self) -> U128 {
+    self.assert_owner();
+    assert!(env::attached_deposit() > 0, "{}", Error::DepositRequired);
+    self.redemption_pool += env::attached_deposit();
+    U128(self.redemption_pool)
+  }
+
+  /// NEAR paid for burning a token of `level`; zero stops paying for it.
+  pub fn set_redemption_amount(
+    &mut self,
+    level: u64,
+    amount: U128,
+  ) {
+    self.assert_owner();
+    if amount.0 == 0 {
+      self.redemption_per_level.remove(&level);
+    } else {
+      self.redemption_per_level.insert(&level, &amount.0);
+    }
+  }
+
+  pub fn get_redemption_amount(
+    &self,
+    level: u64,
+  ) -> U128 {
+    U128(self.redemption_per_level.get(&level).unwrap_or(0))
+  }
+
+  pub fn redemption_pool(&self) -> U128 {
+    U128(self.redemption_pool)
+  }
+
+  /// Sends part of the contract's free balance to `to`. The balance locked
+  /// for storage, which backs every token, the redemption pool and users'
+  /// pre-paid storage credits can never be withdrawn.
+  #[payable]
+  pub fn admin_withdraw(
+    &mut self,
+    amount: U128,
+    to: ValidAccountId,
+  ) -> Promise {
+    assert_one_yocto();
+    self.assert_owner();
+
+    let amount: Balance = amount.into();
+    let storage_reserve = env::storage_byte_cost() * Balance::from(env::storage_usage());
+    let free_balance = env::account_balance()
+      .saturating_sub(storage_reserve)
+      .saturating_sub(self.redemption_pool)
+      .saturating_sub(self.total_storage_credits);
+    assert!(amount > 0, "{}", Error::ZeroAmount);
+    assert!(
+      amount <= free_balance,
+      "{}",
+      Error::InsufficientFreeBalance(free_balance),
+    );
+
+    let to: AccountId = to.into();
+    env::log(format!("Withdrew {} yoctoNEAR to {}", amount, to).as_bytes());
+    Promise::new(to).transfer(amount)
+  }
+
+  pub fn nft_tokens_for_owner(
+    &self,
+    account_id: AccountId,
+    from_index: Option<U128>,
+    limit: Option<u64>
+  ) -> Vec<Token> {
+    let tokens_per_owner = self.tokens.tokens_per_owner.as_ref().unwrap_or_else(|| {
+      panic!("{}", Error::EnumerationDisabled)
+    });
+
+    let token_set = if let Some(token_set) = tokens_per_owner.get(&account_id) {
+      token_set
+    } else {
+      return vec![];
+    };
+
+    self.paginate_token_set(&token_set, from_index, limit)
+  }
+
+  /// `nft_tokens_for_owner` with the owner's total, so clients can show
+  /// "page X of Y" from a single call.
+  pub fn nft_tokens_for_owner_page(
+    &self,
+    account_id: AccountId,
+    from_index: Option<U128>,
+    limit: Option<u64>,
+  ) -> TokenPage {
+    TokenPage {
+      total: U128(self.owner_token_count(&account_id) as u128),
+      tokens: self.nft_tokens_for_owner(account_id, from_index, limit),
+    }
+  }
+
+  /// Filters the owner's tokens before paginating, so `from_index` counts
+  /// matching tokens only. This walks the owner's set linearly and only
+  /// looks at the first `MAX_FILTER_SCAN` tokens of it.
+  pub fn nft_tokens_for_owner_filtered(
+    &self,
+    account_id: AccountId,
+    species: Option<u64>,
+    min_level: Option<u64>,
+    from_index: Option<U128>,
+    limit: Option<u64>,
+  ) -> Vec<Token> {
+    let tokens_per_owner = self.tokens.tokens_per_owner.as_ref().unwrap_or_else(|| {
+      panic!("{}", Error::EnumerationDisabled)
+    });
+
+    let token_set = if let Some(token_set) = tokens_per_owner.get(&account_id) {
+      token_set
+    } else {
+      return vec![];
+    };
+
+    let start = u128::from(from_index.unwrap_or(U128(0)));
+    let limit = limit.unwrap_or(MAX_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+
+    token_set
+      .as_vector()
+      .iter()
+      .take(MAX_FILTER_SCAN)
+      .filter(|token_id| {
+        species.map_or(true, |species| self.egg_per_token_id.get(token_id) == Some(species))
+      })
+      .filter(|token_id| {
+        min_level.map_or(true, |min_level| {
+          self.level_per_token_id.get(token_id).map_or(false, |level| level >= min_level)
+        })
+      })
+      .skip(start as usize)
+      .take(limit as usize)
+      .map(|token_id| self.nft_token(token_id).unwrap())
+      .collect()
+  }
+
+  /// The owner's tokens ordered by level, then token id. Every call loads
+  /// and sorts the whole set, O(n log n), so owners holding more than
+  /// `MAX_SORT_SIZE` tokens are refused.
+  pub fn nft_tokens_for_owner_sorted(
+    &self,
+    account_id: AccountId,
+    ascending: bool,
+    from_index: Option<U128>,
+    limit: Option<u64>,
+  ) -> Vec<Token> {
+    let tokens_per_owner = self.tokens.tokens_per_owner.as_ref().unwrap_or_else(|| {
+      panic!("{}", Error::EnumerationDisabled)
+    });
+
+    let token_set = if let Some(token_set) = tokens_per_owner.get(&account_id) {
+      token_set
+    } else {
+      return vec![];
+    };
+    assert!(
+      token_set.len() <= MAX_SORT_SIZE,
+      "{}",
+      Error::TooManyToSort(MAX_SORT_SIZE as usize),
+    );
+
+    // Ids are decimal counters, so comparing length first keeps them in
+    // numeric order.
+    let mut sorted: Vec<(u64, TokenId)> = token_set
+      .iter()
+      .map(|token_id| (self.level_per_token_id.get(&token_id).unwrap_or(0), token_id))
+      .collect();
+    sorted.sort_by(|(level_a, id_a), (level_b, id_b)| {
+      level_a.cmp(level_b)
+        .then(id_a.len().cmp(&id_b.len()))
+        .then(id_a.cmp(id_b))
+    });
+    if !ascending {
+      sorted.reverse();
+    }
+
+    let start = u128::from(from_index.unwrap_or(U128(0)));
+    let limit = limit.unwrap_or(MAX_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+
+    sorted
+      .into_iter()
+      .skip(start as usize)
+      .take(limit as usize)
+      .map(|(_, token_id)| self.nft_token(token_id).unwrap())
+      .collect()
+  }
+
+  /// Same page as `nft_tokens_for_owner`, without loading metadata or approvals.
+  pub fn token_ids_for_owner(
+    &self,
+    account_id: AccountId,
+    from_index: Option<U128>,
+    limit: Option<u64>,
+  ) -> Vec<TokenId> {
+    let tokens_per_owner = self.tokens.tokens_per_owner.as_ref().unwrap_or_else(|| {
+      panic!("{}", Error::EnumerationDisabled)
+    });
+
+    match tokens_per_owner.get(&account_id) {
+      Some(token_set) => self.paginate_token_ids(&token_set, from_index, limit),
+      None => vec![],
+    }
+  }
+
+  pub fn nft_tokens_by_species(
+    &self,
+    species: u64,
+    from_index: Option<U128>,
+    limit: Option<u64>
+  ) -> Vec<Token> {
+    let token_set = if let Some(token_set) = self.tokens_per_species.get(&species) {
+      token_set
+    } else {
+      return vec![];
+    };
+
+    self.paginate_token_set(&token_set, from_index, limit)
+  }
+
+  pub fn nft_tokens_by_level(
+    &self,
+    level: u64,
+    from_index: Option<U128>,
+    limit: Option<u64>
+  ) -> Vec<Token> {
+    let token_set = if let Some(token_set) = self.tokens_per_level.get(&level) {
+      token_set
+    } else {
+      return vec![];
+    };
+
+    self.paginate_token_set(&token_set, from_index, limit)
+  }
+
+  /// Evolved tokens only, ordered by level and then by position in that
+  /// level's set. Walks `tokens_per_level` from level 1, so eggs are never
+  /// scanned: the cost is one lookup per level plus one per returned token,
+  /// with `limit` capped at `MAX_PAGE_LIMIT`.
+  pub fn nft_tokens_excluding_eggs(
+    &self,
+    from_index: Option<U128>,
+    limit: Option<u64>,
+  ) -> Vec<Token> {
+    let mut levels: Vec<u64> = self.tokens_per_level.keys().filter(|level| *level > 0).collect();
+    levels.sort_unstable();
+
+    let mut start = u128::from(from_index.unwrap_or(U128(0)));
+    let mut remaining = limit.unwrap_or(MAX_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+    let mut tokens = vec![];
+    for level in levels {
+      if remaining == 0 {
+        break;
+      }
+
+      let token_set = self.tokens_per_level.get(&level).unwrap();
+      let len = token_set.len() as u128;
+      if start >= len {
+        start -= len;
+        continue;
+      }
+
+      let token_ids = self.paginate_token_ids(&token_set, Some(U128(start)), Some(remaining));
+      start = 0;
+      remaining -= token_ids.len() as u64;
+      tokens.extend(token_ids.into_iter().map(|token_id| self.nft_token(token_id).unwrap()));
+    }
+
+    tokens
+  }
+
+  /// `nft_tokens_for_owner` with each token's game state and nickname.
+  pub fn tokens_detailed_for_owner(
+    &self,
+    account_id: AccountId,
+    from_index: Option<U128>,
+    limit: Option<u64>,
+  ) -> Vec<DetailedToken> {
+    self.nft_tokens_for_owner(account_id, from_index, limit)
+      .into_iter()
+      .map(|token| DetailedToken {
+        game: self.game_attrs(token.token_id.clone()),
+        nickname: self.nickname_per_token.get(&token.token_id),
+        token,
+      })
+      .collect()
+  }
+
+  /// The owner's evolved tokens. Same scan as `nft_tokens_for_owner_filtered`:
+  /// only the first `MAX_FILTER_SCAN` tokens of the owner's set are looked at.
+  pub fn nft_tokens_for_owner_excluding_eggs(
+    &self,
+    account_id: AccountId,
+    from_index: Option<U128>,
+    limit: Option<u64>,
+  ) -> Vec<Token> {
+    self.nft_tokens_for_owner_filtered(account_id, None, Some(1), from_index, limit)
+  }
+
+  pub fn nft_token(
+    &self, 
+    token_id: TokenId,
+  ) -> Option<Token> {
+    let mut token = self.nft_token_view(token_id)?;
+    // `None` both when approvals are disabled and when the token has none.
+    token.approved_account_ids = self
+      .tokens
+      .approvals_by_id
+      .as_ref()
+      .and_then(|by_id| by_id.get(&token.token_id))
+      .filter(|approvals| !approvals.is_empty());
+
+    Some(token)
+  }
+
+  pub fn contract_source_metadata(&self) -> ContractSourceMetadata {
+    ContractSourceMetadata {
+      version: Some(CONTRACT_SOURCE_VERSION.to_string()),
+      link: Some(CONTRACT_SOURCE_LINK.to_string()),
+      standards: CONTRACT_STANDARDS
+        .iter()
+        .map(|(standard, version)| Standard {
+          standard: standard.to_string(),
+          version: version.to_string(),
+        })
+        .collect(),
+    }
+  }
+
+  /// Just the stored metadata, `None` for unknown tokens.
+  pub fn token_metadata(
+    &self,
+    token_id: TokenId,
+  ) -> Option<TokenMetadata> {
+    self.tokens
+      .token_metadata_by_id
+      .as_ref()
+      .and_then(|by_id| by_id.get(&token_id))
+  }
+
+  /// Species, level, cooldown and template attributes, `None` for unknown
+  /// tokens.
+  pub fn game_attrs(
+    &self,
+    token_id: TokenId,
+  ) -> Option<GameAttrs> {
+    let species = self.egg_per_token_id.get(&token_id)?;
+    let level = self.level_per_token_id.get(&token_id)?;
+    let token_extra = self.token_metadata(token_id)
+      .and_then(|metadata| metadata.extra)
+      .map(|extra| TokenExtra::parse(&extra))?;
+
+    Some(GameAttrs {
+      species,
+      level,
+      evolve_at: U64(token_extra.evolve_at),
+      attrs: token_extra.attrs,
+    })
+  }
+
+  /// `nft_token` without approvals, for indexers that never read them.
+  pub fn nft_token_view(
+    &self,
+    token_id: TokenId,
+  ) -> Option<Token> {
+    let owner_id = self.tokens.owner_by_id.get(&token_id)?;
+    let token_metadata = self
+      .tokens
+      .token_metadata_by_id
+      .as_ref()
+      .and_then(|by_id| by_id.get(&token_id));
+
+    Some(Token {
+      token_id,
+      owner_id,
+      metadata: token_metadata,
+      approved_account_ids: None,
+    })
+  }
+
+  pub fn add_recipe(
+    &mut self,
+    recipe_id: u64,
+    recipe: Recipe,
+  ) {
+    self.assert_owner();
+    assert!(
+      recipe.output_species >= 1 && recipe.output_species <= SPECIES_COUNT,
+      "{}",
+      Error::InvalidSpecies,
+    );
+    assert!(
+      !recipe.inputs.is_empty() && recipe.inputs.len() <= MAX_BATCH,
+      "{}",
+      Error::InvalidRecipeSize(MAX_BATCH),
+    );
+
+    self.recipes.insert(&recipe_id, &recipe);
+  }
+
+  pub fn remove_recipe(
+    &mut self,
+    recipe_id: u64,
+  ) {
+    self.assert_owner();
+    self.recipes.remove(&recipe_id);
+  }
+
+  pub fn get_recipe(
+    &self,
+    recipe_id: u64,
+  ) -> Option<Recipe> {
+    self.recipes.get(&recipe_id)
+  }
+
+  #[payable]
+  pub fn craft(
+    &mut self,
+    token_ids: Vec<TokenId>,
+    recipe_id: u64,
+  ) -> TokenId {
+    assert_batch_size(token_ids.len(), MAX_BATCH);
+    let recipe = self.recipes.get(&recipe_id).unwrap_or_else(|| panic!("{}", Error::RecipeNotFound));
+    let owner_id = env::predecessor_account_id();
+
+    let mut unique_ids = token_ids.clone();
+    unique_ids.sort();
+    unique_ids.dedup();
+    assert_eq!(unique_ids.len(), token_ids.len(), "{}", Error::DuplicateTokenId);
+
+    let mut given: Vec<RecipeInput> = token_ids
+      .iter()
+      .map(|token_id| {
+        assert_eq!(
+          self.tokens.owner_by_id.get(token_id).unwrap_or_else(|| panic!("{}", Error::TokenNotFound)),
+          owner_id,
+          "{}",
+          Error::NotTokenOwner,
+        );
+        self.assert_not_locked(token_id);
+        RecipeInput {
+          species: self.egg_per_token_id.get(token_id).unwrap(),
+          level: self.level_per_token_id.get(token_id).unwrap(),
+        }
+      })
+      .collect();
+    let mut required = recipe.inputs.clone();
+    given.sort();
+    required.sort();
+    assert!(given == required, "{}", Error::RecipeMismatch);
+
+    for token_id in token_ids.iter() {
+      self.internal_burn(token_id, &owner_id);
+    }
+
+    let token_id = self.internal_mint_egg_of_species(owner_id.clone(), recipe.output_species);
+
+    events::emit(EventLogVariant::NftBurn(vec![NftBurnLog {
+      owner_id: owner_id.clone(),
+      token_ids,
+      memo: Some(format!("crafted into {}", token_id)),
+    }]));
+    events::emit(EventLogVariant::NftMint(vec![NftMintLog {
+      owner_id,
+      token_ids: vec![token_id.clone()],
+      memo: Some(format!("recipe {}", recipe_id)),
+    }]));
+
+    refund_deposit(MINIMUM_EARLY_DEPOSIT);
+
+    token_id
+  }
+
+  pub fn add_approved_locker(
+    &mut self,
+    locker_id: ValidAccountId,
+  ) {
+    self.assert_owner();
+    self.approved_lockers.insert(locker_id.as_ref());
+  }
+
+  pub fn remove_approved_locker(
+    &mut self,
+    locker_id: ValidAccountId,
+  ) {
+    self.assert_owner();
+    self.approved_lockers.remove(locker_id.as_ref());
+  }
+
+  /// Called by the token owner to hand the lock to an approved locker
+  /// (e.g. a staking contract); only that locker can unlock it.
+  pub fn lock_token(
+    &mut self,
+    token_id: TokenId,
+    locker_id: ValidAccountId,
+  ) {
+    let locker_id: AccountId = locker_id.into();
+    assert!(self.approved_lockers.contains(&locker_id), "{}", Error::LockerNotApproved);
+
+    let owner_id = self.tokens.owner_by_id.get(&token_id).unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+    assert_eq!(
+      owner_id,
+      env::predecessor_account_id(),
+      "{}",
+      Error::LockNotRequestedByOwner,
+    );
+    assert!(self.locked_tokens.get(&token_id).is_none(), "{}", Error::TokenAlreadyLocked);
+
+    self.locked_tokens.insert(&token_id, &locker_id);
+  }
+
+  pub fn unlock_token(
+    &mut self,
+    token_id: TokenId,
+  ) {
+    let locker_id = self.locked_tokens.get(&token_id).unwrap_or_else(|| panic!("{}", Error::TokenNotLocked));
+    assert_eq!(
+      locker_id,
+      env::predecessor_account_id(),
+      "{}",
+      Error::NotLocker,
+    );
+
+    self.locked_tokens.remove(&token_id);
+  }
+
+  pub fn is_locked(
+    &self,
+    token_id: TokenId,
+  ) -> bool {
+    self.locked_tokens.get(&token_id).is_some()
+  }
+
+  /// Starts the timelock for moving a stuck token to `new_owner`. A contract
+  /// cannot tell synchronously whether an account was deleted, so the delay
+  /// gives a live owner time to object with `cancel_token_rescue` or simply
+  /// by transferring the token.
+  pub fn propose_token_rescue(
+    &mut self,
+    token_id: TokenId,
+    new_owner: ValidAccountId,
+  ) -> U64 {
+    self.assert_owner();
+    let stuck_owner_id = self.tokens.owner_by_id
+      .get(&token_id)
+      .unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+
+    let ready_at = env::block_timestamp() / 1000000 + RESCUE_TIMELOCK_MS;
+    self.rescue_per_token.insert(&token_id, &TokenRescue {
+      stuck_owner_id,
+      new_owner_id: new_owner.into(),
+      ready_at,
+    });
+
+    U64(ready_at)
+  }
+
+  /// Callable by the contract owner or by the token owner, whose call alone
+  /// shows the account is still alive.
+  pub fn cancel_token_rescue(
+    &mut self,
+    token_id: TokenId,
+  ) {
+    let owner_id = self.tokens.owner_by_id
+      .get(&token_id)
+      .unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+    let caller_id = env::predecessor_account_id();
+    if caller_id != owner_id {
+      self.assert_owner();
+    }
+
+    self.rescue_per_token.remove(&token_id);
+  }
+
+  pub fn get_token_rescue(
+    &self,
+    token_id: TokenId,
+  ) -> Option<TokenRescue> {
+    self.rescue_per_token.get(&token_id)
+  }
+
+  /// Moves a stuck token once its proposed rescue has matured, provided the
+  /// owner has not changed in the meantime. Goes through the regular
+  /// transfer path, so the per-owner sets and counts move with it and
+  /// approvals and co-owners are cleared; the `nft_transfer` event carries
+  /// the contract owner as `authorized_id` and the memo "admin rescue".
+  pub fn admin_reassign_token(
+    &mut self,
+    token_id: TokenId,
+    new_owner: ValidAccountId,
+  ) {
+    self.assert_owner();
+    let rescue = self.rescue_per_token
+      .get(&token_id)
+      .filter(|rescue| &rescue.new_owner_id == new_owner.as_ref())
+      .unwrap_or_else(|| panic!("{}", Error::RescueNotProposed));
+    assert!(
+      env::block_timestamp() / 1000000 >= rescue.ready_at,
+      "{}",
+      Error::RescueNotReady(rescue.ready_at),
+    );
+
+    let owner_id = self.tokens.owner_by_id
+      .get(&token_id)
+      .unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+    assert_eq!(owner_id, rescue.stuck_owner_id, "{}", Error::RescueOwnerChanged);
+
+    self.internal_move_token(&owner_id, new_owner.as_ref(), &token_id, None);
+    self.approval_expiry_by_id.remove(&token_id);
+
+    events::emit(EventLogVariant::NftTransfer(vec![NftTransferLog {
+      authorized_id: Some(self.owner_id.clone()),
+      old_owner_id: owner_id,
+      new_owner_id: new_owner.into(),
+      token_ids: vec![token_id],
+      memo: Some(RESCUE_MEMO.to_string()),
+    }]));
+  }
+
+  pub fn can_evolve(
+    &self,
+    token_id: TokenId,
+  ) -> bool {
+    self.evolve_blockers(token_id).is_empty()
+  }
+
+  /// Human readable reasons `token_id` cannot evolve right now.
+  pub fn evolve_blockers(
+    &self,
+    token_id: TokenId,
+  ) -> Vec<String> {
+    let (owner_id, level) = match (self.tokens.owner_by_id.get(&token_id), self.level_per_token_id.get(&token_id)) {
+      (Some(owner_id), Some(level)) => (owner_id, level),
+      _ => return vec!["Token not found".to_string()],
+    };
+
+    let mut blockers = vec![];
+
+    let species = self.egg_per_token_id.get(&token_id).unwrap();
+    let species_capped = self.max_level_per_species
+      .get(&species)
+      .map_or(false, |max_level| level >= max_level);
+    if species_capped {
+      blockers.push("This species is fully evolved".to_string());
+    } else if !self.has_template_for_level(level + 1, species) {
+      blockers.push("Token has reached its maximum level".to_string());
+    }
+
+    if let Some((item_contract, amount)) = self.required_item_per_level.get(&(level + 1)) {
+      if self.item_balances.get(&(owner_id, item_contract.clone())).unwrap_or(0) < amount {
+        blockers.push(format!("Evolving needs {} of {}", amount, item_contract));
+      }
+    }
+
+    let evolve_at = self.tokens.token_metadata_by_id
+      .as_ref()
+      .and_then(|by_id| by_id.get(&token_id))
+      .and_then(|metadata| metadata.extra)
+      .map(|extra| TokenExtra::parse(&extra).evolve_at);
+    match evolve_at {
+      Some(evolve_at) if env::block_timestamp() / 1000000 < evolve_at => {
+        blockers.push(format!("Evolve cooldown ends at {}", evolve_at));
+      }
+      None => blockers.push("Token has no metadata".to_string()),
+      _ => {}
+    }
+
+    if self.locked_tokens.get(&token_id).is_some() {
+      blockers.push("Token is locked".to_string());
+    }
+
+    if self.unrevealed_eggs.contains(&token_id) {
+      blockers.push("Egg species is not revealed yet".to_string());
+    }
+
+    blockers
+  }
+
+  pub fn owner_of(
+    &self,
+    token_id: TokenId,
+  ) -> Option<AccountId> {
+    self.tokens.owner_by_id.get(&token_id)
+  }
+
+  /// An empty request is answered with an empty list, as before batches
+  /// were bounded; only the upper bound applies to this view.
+  pub fn nft_tokens_batch(
+    &self,
+    token_ids: Vec<TokenId>,
+  ) -> Vec<Option<Token>> {
+    if token_ids.is_empty() {
+      return vec![];
+    }
+    assert_batch_size(token_ids.len(), MAX_BATCH);
+
+    token_ids
+      .into_iter()
+      .map(|token_id| self.nft_token(token_id))
+      .collect()
+  }
+
+  pub fn total_owners(&self) -> u64 {
+    self.count_per_owner.len()
+  }
+
+  pub fn nft_supply_for_owner(
+    &self,
+    account_id: AccountId,
+  ) -> U128 {
+    U128(self.owner_token_count(&account_id) as u128)
+  }
+
+  pub fn get_owner(&self) -> AccountId {
+    self.tokens.owner_id.clone()
+  }
+
+  /// Add new settings here as well as giving them their own getter.
+  pub fn get_config(&self) -> ContractConfig {
+    ContractConfig {
+      owner_id: self.get_owner(),
+      mint_fee: U128(MINIMUM_EARLY_DEPOSIT),
+      evolve_fee: self.get_evolve_fee(),
+      reroll_fee: self.get_reroll_fee(),
+      max_supply: self.get_max_supply(),
+      max_mints_per_account: self.get_max_mints_per_account(),
+      sale_start_ms: self.sale_start_ms.map(U64),
+      sale_end_ms: self.sale_end_ms.map(U64),
+      species_count: SPECIES_COUNT,
+      deterministic_mint: self.deterministic_mint,
+      metadata_frozen: self.is_metadata_frozen(),
+      game_contract_id: self.game_contract_id.clone(),
+      registry_contract: self.registry_contract.clone(),
+      renderer_base: self.get_renderer_base(),
+      treasury: self.treasury.clone(),
+      treasury_bps: self.treasury_bps,
+      base_cooldown_ms: self.base_cooldown_ms.map(U64),
+      cooldown_multiplier_bps: self.cooldown_multiplier_bps,
+      templates_required: self.templates_required,
+      default_media: self.default_media.clone(),
+    }
+  }
+
+  pub fn nft_total_supply(&self) -> U128 {
+    U128(self.tokens.owner_by_id.len() as u128)
+  }
+
+  /// Every species in id order, with its roll weight, live supply and level
+  /// cap.
+  pub fn species_catalog(&self) -> Vec<SpeciesEntry> {
+    self.species_weights
+      .iter()
+      .enumerate()
+      .map(|(index, weight)| {
+        let species = index as u64 + 1;
+        SpeciesEntry {
+          species,
+          weight: *weight,
+          supply: self.supply_for_species(species),
+          max_level: self.get_max_level_for_species(species),
+        }
+      })
+      .collect()
+  }
+
+  /// Bundles `nft_metadata`, `get_owner`, `get_config`, `nft_total_supply`
+  /// and `species_catalog`.
+  pub fn bootstrap(&self) -> Bootstrap {
+    Bootstrap {
+      metadata: self.nft_metadata(),
+      owner_id: self.get_owner(),
+      config: self.get_config(),
+      total_supply: self.nft_total_supply(),
+      species: self.species_catalog(),
+    }
+  }
+
+  pub fn metadata_type_list(&self) -> Vec<String> {
+    let metadata_vector = self.metadata_per_type.keys_as_vector();
+    metadata_vector.to_vec()
+  }
+
+  pub fn metadata_per_type_list(
+    &self,
+    metadata_type: MetadataType,
+  ) -> Vec<TokenMetadata> {
+    let metadata_vector = if let Some(metadata_set) = self.metadata_per_type.get(&metadata_type) {
+      metadata_set
+    } else {
+      return vec![];
+    };
+
+    metadata_vector.to_vec()
+  }
+
+  /// Every registered template, flattened across types in map order.
+  pub fn all_metadata(
+    &self,
+    from_index: Option<U128>,
+    limit: Option<u64>,
+  ) -> Vec<(MetadataType, TokenMetadata)> {
+    let start = u128::from(from_index.unwrap_or(U128(0)));
+    let limit = limit.unwrap_or(MAX_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+
+    self.metadata_per_type
+      .iter()
+      .flat_map(|(metadata_type, metadata_set)| {
+        metadata_set
+          .to_vec()
+          .into_iter()
+          .map(move |metadata| (metadata_type.clone(), metadata))
+      })
+      .skip(start as usize)
+      .take(limit as usize)
+      .collect()
+  }
+
+  pub fn supply_for_species(
+    &self,
+    species: u64,
+  ) -> U128 {
+    U128(self.supply_per_species.get(&species).unwrap_or(0) as u128)
+  }
+
+  pub fn supply_per_species_all(&self) -> Vec<(u64, U128)> {
+    self.supply_per_species
+      .iter()
+      .map(|(species, supply)| (species, U128(supply as u128)))
+      .collect()
+  }
+
+  /// Live token count per level, eggs being level 0, sorted by level.
+  /// Levels nobody currently holds are left out.
+  pub fn supply_per_level(&self) -> Vec<(u64, U128)> {
+    let mut supply: Vec<(u64, U128)> = self.supply_per_level
+      .iter()
+      .map(|(level, supply)| (level, U128(supply as u128)))
+      .collect();
+    supply.sort_by_key(|(level, _)| *level);
+    supply
+  }
+
+  /// When the lineage's original egg was minted, in milliseconds. Unlike
+  /// `issued_at`, this survives evolution.
+  pub fn minted_at(
+    &self,
+    token_id: TokenId,
+  ) -> Option<U64> {
+    let egg_id = self.evolution_history(token_id).remove(0);
+    self.minted_at_per_lineage.get(&egg_id).map(U64)
+  }
+
+  /// Token ids from the original egg up to and including `token_id`.
+  pub fn evolution_history(
+    &self,
+    token_id: TokenId,
+  ) -> Vec<TokenId> {
+    let mut history = vec![token_id.clone()];
+    let mut current = token_id;
+
+    while let Some(previous) = self.lineage_per_token_id.get(&current) {
+      history.push(previous.clone());
+      current = previous;
+    }
+
+    history.reverse();
+    history
+  }
+
+  /// XP follows the token through evolution.
+  pub fn xp_for_token(
+    &self,
+    token_id: TokenId,
+  ) -> U64 {
+    U64(self.xp_per_token_id.get(&token_id).unwrap_or(0))
+  }
+
+  pub fn grant_xp(
+    &mut self,
+    token_id: TokenId,
+    amount: u64,
+  ) -> U64 {
+    self.assert_owner();
+    assert!(self.tokens.owner_by_id.get(&token_id).is_some(), "{}", Error::TokenNotFound);
+
+    U64(self.internal_grant_xp(&token_id, amount))
+  }
+
+  /// Grants every increment it can and returns the ids that were skipped
+  /// because the token does not exist.
+  pub fn grant_xp_batch(
+    &mut self,
+    grants: Vec<(TokenId, u64)>,
+  ) -> Vec<TokenId> {
+    self.assert_owner();
+    assert_batch_size(grants.len(), MAX_BATCH);
+
+    let mut skipped = vec![];
+    for (token_id, amount) in grants {
+      if self.tokens.owner_by_id.get(&token_id).is_some() {
+        self.internal_grant_xp(&token_id, amount);
+      } else {
+        skipped.push(token_id);
+      }
+    }
+
+    skipped
+  }
+
+  pub fn level_per_token(
+    &self, 
+    token_id: TokenId,
+  ) -> u64 {
+    self.level_per_token_id.get(&token_id).unwrap()
+  }
+
+  fn internal_mint_egg(
+    &mut self,
+    receiver_id: AccountId,
+    random_source: &dyn RandomSource,
+  ) -> TokenId {
+    let metadata_set = self.roll_species(random_source);
+    self.internal_mint_egg_of_species(receiver_id, metadata_set)
+  }
+
+  fn roll_species(
+    &self,
+    random_source: &dyn RandomSource,
+  ) -> u64 {
+    let total_weight: u64 = self.species_weights.iter().sum();
+    let mut roll = self.get_random_number(random_source, total_weight);
+
+    for (index, weight) in self.species_weights.iter().enumerate() {
+      if roll < *weight {
+        return index as u64 + 1;
+      }
+      roll -= weight;
+    }
+
+    SPECIES_COUNT
+  }
+
+  /// Swaps an egg's species for a fresh roll, keeping its id, cooldown and
+  /// attributes.
+  fn internal_reroll_egg(
+    &mut self,
+    token_id: &TokenId,
+    random_source: &dyn RandomSource,
+  ) -> u64 {
+    let owner_id = self.tokens.owner_by_id
+      .get(token_id)
+      .unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+    assert_eq!(owner_id, env::predecessor_account_id(), "{}", Error::NotTokenOwner);
+    assert_eq!(self.level_per_token_id.get(token_id), Some(0), "{}", Error::NotAnEgg);
+    assert!(!self.unrevealed_eggs.contains(token_id), "{}", Error::SpeciesNotRevealed);
+    self.assert_not_locked(token_id);
+
+    let new_species = self.roll_species(random_source);
+    self.internal_set_egg_species(token_id, new_species);
+
+    new_species
+  }
+
+  /// Swaps an egg's species in every index and its metadata, keeping its
+  /// id, cooldown and attributes.
+  fn internal_set_egg_species(
+    &mut self,
+    token_id: &TokenId,
+    new_species: u64,
+  ) {
+    if self.templates_required {
+      assert!(self.has_registered_template(0, new_species), "{}", Error::SpeciesNotConfigured(new_species));
+    }
+    let old_species = self.egg_per_token_id.get(token_id).unwrap();
+
+    self.internal_remove_token_from_species(old_species, token_id);
+    let old_supply = self.supply_per_species.get(&old_species).unwrap_or(0);
+    self.supply_per_species.insert(&old_species, &old_supply.saturating_sub(1));
+
+    self.egg_per_token_id.insert(token_id, &new_species);
+    self.internal_add_token_to_species(new_species, token_id);
+    let new_supply = self.supply_per_species.get(&new_species).unwrap_or(0);
+    self.supply_per_species.insert(&new_species, &(new_supply + 1));
+
+    let previous_extra = self.token_metadata(token_id.clone())
+      .and_then(|metadata| metadata.extra)
+      .map(|extra| TokenExtra::parse(&extra));
+    let mut metadata = self.get_metadata_per_type(0, new_species);
+    if let Some(previous_extra) = previous_extra {
+      let mut token_extra = TokenExtra::parse(metadata.extra.as_ref().unwrap());
+      token_extra.evolve_at = previous_extra.evolve_at;
+      if token_extra.attrs.is_none() {
+        token_extra.attrs = previous_extra.attrs;
+      }
+      metadata.extra = Some(serde_json::to_string(&token_extra).unwrap());
+    }
+
+    if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
+      token_metadata_by_id.insert(token_id, &metadata);
+    }
+  }
+
+  fn internal_mint_egg_of_species(
+    &mut self,
+    receiver_id: AccountId,
+    metadata_set: u64,
+  ) -> TokenId {
+    assert!(
+      env::is_valid_account_id(receiver_id.as_bytes()),
+      "{}",
+      Error::InvalidReceiver,
+    );
+    if let Some(max_supply) = self.max_supply {
+      assert!(self.total_minted < max_supply, "{}", Error::MaxSupplyReached(max_supply));
+    }
+    if self.templates_required {
+      assert!(self.has_registered_template(0, metadata_set), "{}", Error::SpeciesNotConfigured(metadata_set));
+    }
+    self.total_minted += 1;
+
+    let token_id = self.increment_token_id();
+
+    let metadata_type = 0u64;
+    let owner_id: AccountId = receiver_id;
+
+    let metadata: TokenMetadata = self.get_metadata_per_type(metadata_type, metadata_set);
+
+    self.tokens.owner_by_id.insert(&token_id, &owner_id);
+
+    self.egg_per_token_id.insert(&token_id, &metadata_set);
+
+    self.internal_add_token_to_species(metadata_set, &token_id);
+
+    let species_supply = self.supply_per_species.get(&metadata_set).unwrap_or(0);
+    self.supply_per_species.insert(&metadata_set, &(species_supply + 1));
+
+    self.level_per_token_id.insert(&token_id, &0u64);
+    self.internal_add_token_to_level(0, &token_id);
+
+    self.minted_at_per_lineage.insert(&token_id, &(env::block_timestamp() / 1000000));
+    
+    self.tokens
+      .token_metadata_by_id
+      .as_mut()
+      .and_then(|by_id| by_id.insert(&token_id, &metadata));
+
+    if let Some(mut token_ids) = self.internal_owner_token_set(&owner_id) {
+      token_ids.insert(&token_id);
+      self.internal_set_owner_token_set(&owner_id, &token_ids);
+    }
+
+    self.internal_add_token_to_owner_count(&owner_id);
+
+    token_id
+  }
+
+  /// The evolved token always goes to the caller, who must own `token_id`.
+  ///
+  /// Every precondition, including which template the new token gets, is
+  /// checked before the first write, so a retried evolve on an id that was
+  /// already consumed fails without touching state.
+  fn internal_evolve(
+    &mut self,
+    token_id: TokenId,
+    branch: Option<u64>,
+  ) -> TokenId {
+    // The cooldown lives in the token metadata, so evolving without the
+    // store would skip it entirely; `new` always creates it.
+    let token_metadata_by_id = self.tokens.token_metadata_by_id
+      .as_ref()
+      .unwrap_or_else(|| panic!("{}", Error::MetadataStoreMissing));
+    let owner_id = self.internal_live_token_owner(&token_id);
+    assert_eq!(
+      owner_id,
+      env::predecessor_account_id(),
+      "{}",
+      Error::NotTokenOwner,
+    );
+
+    let token_extra = TokenExtra::parse(&token_metadata_by_id.get(&token_id).unwrap().extra.unwrap());
+    if env::block_timestamp() / 1000000 < token_extra.evolve_at {
+      panic!("{}", Error::EvolveCooldown);
+    }
+    let previous_attrs = token_extra.attrs;
+
+    self.assert_metadata_matches_level(&token_id);
+    self.assert_not_locked(&token_id);
+    assert!(!self.unrevealed_eggs.contains(&token_id), "{}", Error::SpeciesNotRevealed);
+    let metadata_set = self.egg_per_token_id.get(&token_id).unwrap();
+    let token_level = self.level_per_token_id.get(&token_id).unwrap() + 1;
+    if let Some(max_level) = self.max_level_per_species.get(&metadata_set) {
+      assert!(token_level <= max_level, "{}", Error::SpeciesFullyEvolved);
+    }
+    let forms = self.evolve_branches.get(&(metadata_set, token_level));
+    let chosen_branch = forms.as_ref().map(|_| branch.unwrap_or(0));
+    let template = match forms {
+      Some(forms) => forms
+        .get(branch.unwrap_or(0) as usize)
+        .cloned()
+        .unwrap_or_else(|| panic!("{}", Error::InvalidBranch)),
+      None => {
+        assert_eq!(branch.unwrap_or(0), 0, "{}", Error::InvalidBranch);
+        self.resolve_template(token_level, metadata_set)
+          .unwrap_or_else(|| panic!("{}", Error::MaxLevelReached))
+      }
+    };
+
+    self.internal_consume_evolution_item(&owner_id, token_level);
+    let new_token_id = self.increment_token_id();
+
+    if let Some(next_approval_id_by_id) = &mut self.tokens.next_approval_id_by_id {
+      next_approval_id_by_id.remove(&token_id);
+    }
+
+    if let Some(approvals_by_id) = &mut self.tokens.approvals_by_id {
+      approvals_by_id.remove(&token_id);
+    }
+    self.approval_expiry_by_id.remove(&token_id);
+    self.rescue_per_token.remove(&token_id);
+
+    if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
+      token_metadata_by_id.remove(&token_id);
+    }
+
+    self.egg_per_token_id.remove(&token_id);
+    self.egg_per_token_id.insert(&new_token_id, &metadata_set);
+
+    // Lineage is history: the consumed id keeps its link to its own parent,
+    // so `evolution_history` can walk back to the egg.
+    self.lineage_per_token_id.insert(&new_token_id, &token_id);
+    if let Some(xp) = self.xp_per_token_id.remove(&token_id) {
+      self.xp_per_token_id.insert(&new_token_id, &xp);
+    }
+    if let Some(co_owners) = self.co_owners_per_token.remove(&token_id) {
+      self.co_owners_per_token.insert(&new_token_id, &co_owners);
+    }
+    if let Some(nickname) = self.nickname_per_token.remove(&token_id) {
+      self.nickname_per_token.insert(&new_token_id, &nickname);
+    }
+    self.branch_per_token.remove(&token_id);
+    if let Some(chosen_branch) = chosen_branch {
+      self.branch_per_token.insert(&new_token_id, &chosen_branch);
+    }
+    self.internal_remove_token_from_species(metadata_set, &token_id);
+    self.internal_add_token_to_species(metadata_set, &new_token_id);
+
+    self.level_per_token_id.remove(&token_id);
+    self.level_per_token_id.insert(&new_token_id, &token_level);    
+
+    self.internal_remove_token_from_level(token_level - 1, &token_id);
+    self.internal_add_token_to_level(token_level, &new_token_id);
+
+    let mut metadata = self.stamp_metadata(template, token_level, metadata_set);
+    let mut token_extra = TokenExtra::parse(metadata.extra.as_ref().unwrap());
+    if token_extra.attrs.is_none() {
+      token_extra.attrs = previous_attrs;
+      metadata.extra = Some(serde_json::to_string(&token_extra).unwrap());
+    }
+
+    self.tokens.owner_by_id.remove(&token_id);
+    self.tokens.owner_by_id.insert(&new_token_id, &owner_id);
+
+    self.tokens
+      .token_metadata_by_id
+      .as_mut()
+      .and_then(|by_id| by_id.insert(&new_token_id, &metadata));
+
+    if let Some(mut token_ids) = self.internal_owner_token_set(&owner_id) {
+      token_ids.remove(&token_id);
+      token_ids.insert(&new_token_id);
+      self.internal_set_owner_token_set(&owner_id, &token_ids);
+    }
+
+    new_token_id
+  }
+
+  /// Covers `required_cost` from the account's storage credit first and
+  /// the attached deposit for the rest.
+  fn charge_with_storage_credit(
+    &mut self,
+    account_id: &AccountId,
+    required_cost: Balance,
+  ) {
+    let credit = self.storage_credits.get(account_id).unwrap_or(0);
+    let from_credit = credit.min(required_cost);
+    if from_credit > 0 {
+      self.set_storage_credit(account_id, credit - from_credit);
+    }
+
+    refund_deposit(required_cost - from_credit);
+  }
+
+  /// Sends the treasury its cut of `fee`. Only what is left after paying
+  /// for `storage_used` is split, so storage-locked funds never leave.
+  fn pay_treasury(
+    &self,
+    fee: Balance,
+    storage_used: StorageUsage,
+  ) {
+    if let Some(treasury) = &self.treasury {
+      let surplus = fee.saturating_sub(env::storage_byte_cost() * Balance::from(storage_used));
+      let share = surplus * Balance::from(self.treasury_bps) / Balance::from(MAX_BPS);
+      if share > 0 {
+        Promise::new(treasury.clone()).transfer(share);
+      }
+    }
+  }
+
+  fn set_storage_credit(
+    &mut self,
+    account_id: &AccountId,
+    credit: Balance,
+  ) {
+    let previous = self.storage_credits.get(account_id).unwrap_or(0);
+    self.total_storage_credits = self.total_storage_credits - previous + credit;
+    if credit == 0 {
+      self.storage_credits.remove(account_id);
+    } else {
+      self.storage_credits.insert(account_id, &credit);
+    }
+  }
+
+  fn internal_grant_xp(
+    &mut self,
+    token_id: &TokenId,
+    amount: u64,
+  ) -> u64 {
+    let xp = self.xp_per_token_id.get(token_id).unwrap_or(0).saturating_add(amount);
+    self.xp_per_token_id.insert(token_id, &xp);
+    xp
+  }
+
+  /// Approves `account_id` on every token, charging the caller for the new
+  /// storage, and notifies it with `nft_on_approve` when `msg` is given.
+  fn internal_approve_many(
+    &mut self,
+    token_ids: Vec<TokenId>,
+    account_id: AccountId,
+    expires_at: Option<u64>,
+    msg: Option<String>,
+  ) -> Vec<u64> {
+    assert!(env::attached_deposit() > 0, "{}", Error::DepositRequired);
+    assert_batch_size(token_ids.len(), MAX_APPROVE_BATCH);
+
+    let initial_storage_usage = env::storage_usage();
+    let owner_id = env::predecessor_account_id();
+
+    let approval_ids: Vec<u64> = token_ids
+      .iter()
+      .map(|token_id| self.internal_approve(token_id, &owner_id, &account_id, expires_at))
+      .collect();
+
+    let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+    refund_deposit(env::storage_byte_cost() * Balance::from(storage_used));
+
+    if let Some(msg) = msg {
+      for (token_id, approval_id) in token_ids.into_iter().zip(approval_ids.iter()) {
+        ext_non_fungible_approval_receiver::nft_on_approve(
+          token_id,
+          owner_id.clone(),
+          *approval_id,
+          msg.clone(),
+          &account_id,
+          NO_DEPOSIT,
+          GAS_FOR_NFT_ON_APPROVE,
+        );
+      }
+    }
+
+    approval_ids
+  }
+
+  fn internal_approve(
+    &mut self,
+    token_id: &TokenId,
+    owner_id: &AccountId,
+    account_id: &AccountId,
+    expires_at: Option<u64>,
+  ) -> u64 {
+    let token_owner_id = self.tokens.owner_by_id
+      .get(token_id)
+      .unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+    assert_eq!(&token_owner_id, owner_id, "{}", Error::NotTokenOwner);
+
+    let next_approval_id_by_id = self.tokens.next_approval_id_by_id
+      .as_mut()
+      .unwrap_or_else(|| panic!("{}", Error::ApprovalsDisabled));
+    let approval_id = next_approval_id_by_id.get(token_id).unwrap_or(1);
+    next_approval_id_by_id.insert(token_id, &(approval_id + 1));
+
+    let approvals_by_id = self.tokens.approvals_by_id
+      .as_mut()
+      .unwrap_or_else(|| panic!("{}", Error::ApprovalsDisabled));
+    let mut approvals = approvals_by_id.get(token_id).unwrap_or_default();
+    approvals.insert(account_id.clone(), approval_id);
+    approvals_by_id.insert(token_id, &approvals);
+
+    let mut expiries = self.approval_expiry_by_id.get(token_id).unwrap_or_default();
+    let had_expiry = expiries.remove(account_id).is_some();
+    if let Some(expires_at) = expires_at {
+      expiries.insert(account_id.clone(), (approval_id, expires_at));
+    }
+    if expires_at.is_some() || had_expiry {
+      self.internal_set_approval_expiries(token_id, expiries);
+    }
+
+    approval_id
+  }
+
+  fn internal_set_approval_expiries(
+    &mut self,
+    token_id: &TokenId,
+    expiries: HashMap<AccountId, (u64, u64)>,
+  ) {
+    if expiries.is_empty() {
+      self.approval_expiry_by_id.remove(token_id);
+    } else {
+      self.approval_expiry_by_id.insert(token_id, &expiries);
+    }
+  }
+
+  /// Whether `account_id`'s approval `approval_id` on `token_id` has lapsed.
+  fn is_approval_expired(
+    &self,
+    token_id: &TokenId,
+    account_id: &AccountId,
+    approval_id: u64,
+  ) -> bool {
+    self.approval_expiry_by_id
+      .get(token_id)
+      .and_then(|expiries| expiries.get(account_id).copied())
+      .map(|(expiring_id, expires_at)| {
+        expiring_id == approval_id && env::block_timestamp() / 1000000 >= expires_at
+      })
+      .unwrap_or(false)
+  }
+
+  fn internal_consume_evolution_item(
+    &mut self,
+    owner_id: &AccountId,
+    level: u64,
+  ) {
+    if let Some((item_contract, amount)) = self.required_item_per_level.get(&level) {
+      let balance = self.item_balances.get(&(owner_id.clone(), item_contract.clone())).unwrap_or(0);
+      let remaining = balance
+        .checked_sub(amount)
+        .unwrap_or_else(|| panic!("{}", Error::MissingEvolutionItem(item_contract.clone(), amount)));
+      self.internal_set_item_balance(owner_id, &item_contract, remaining);
+    }
+  }
+
+  /// Stores `balance`, dropping the entry once it is zero.
+  fn internal_set_item_balance(
+    &mut self,
+    account_id: &AccountId,
+    item_contract: &AccountId,
+    balance: Balance,
+  ) {
+    let key = (account_id.clone(), item_contract.clone());
+    if balance == 0 {
+      self.item_balances.remove(&key);
+    } else {
+      self.item_balances.insert(&key, &balance);
+    }
+  }
+
+  /// Owner of `token_id`, telling an id consumed by evolve or burn apart
+  /// from one that was never minted.
+  fn internal_live_token_owner(
+    &self,
+    token_id: &TokenId,
+  ) -> AccountId {
+    self.tokens.owner_by_id.get(token_id).unwrap_or_else(|| {
+      let was_minted = token_id.parse::<u64>().map_or(false, |id| id >= 1 && id <= self.current_token_id);
+      if was_minted {
+        panic!("{}", Error::TokenNoLongerExists)
+      }
+      panic!("{}", Error::TokenNotFound)
+    })
+  }
+
+  /// The owner's token set, or a fresh one under `tokens_per_owner_key`;
+  /// `None` only when enumeration is disabled.
+  fn internal_owner_token_set(
+    &self,
+    owner_id: &AccountId,
+  ) -> Option<UnorderedSet<TokenId>> {
+    self.tokens.tokens_per_owner.as_ref().map(|tokens_per_owner| {
+      tokens_per_owner
+        .get(owner_id)
+        .unwrap_or_else(|| UnorderedSet::new(tokens_per_owner_key(owner_id)))
+    })
+  }
+
+  /// Stores `token_set` for `owner_id`, dropping the entry once it is empty.
+  fn internal_set_owner_token_set(
+    &mut self,
+    owner_id: &AccountId,
+    token_set: &UnorderedSet<TokenId>,
+  ) {
+    if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
+      if token_set.is_empty() {
+        tokens_per_owner.remove(owner_id);
+      } else {
+        tokens_per_owner.insert(owner_id, token_set);
+      }
+    }
+  }
+
+  fn internal_regenerate_metadata(
+    &mut self,
+    token_id: &TokenId,
+  ) {
+    let species = self.egg_per_token_id.get(token_id).unwrap();
+    let level = self.level_per_token_id.get(token_id).unwrap_or(0);
+    let branch_form = self.branch_per_token.get(token_id).and_then(|branch| {
+      self.evolve_branches
+        .get(&(species, level))
+        .and_then(|forms| forms.get(branch as usize).cloned())
+    });
+    let template = branch_form
+      .or_else(|| self.resolve_template(level, species))
+      .unwrap_or_else(|| panic!("{}", Error::UnknownLevel(level)));
+
+    let previous = self.tokens.token_metadata_by_id.as_ref().and_then(|by_id| by_id.get(token_id));
+    if let Some(previous) = previous {
+      let previous_extra = TokenExtra::parse(previous.extra.as_ref().unwrap());
+
+      let mut metadata = self.render_metadata(template, level, species, previous.copies.unwrap_or(1));
+      let mut token_extra = TokenExtra::parse(metadata.extra.as_ref().unwrap());
+      token_extra.evolve_at = previous_extra.evolve_at;
+      if token_extra.attrs.is_none() {
+        token_extra.attrs = previous_extra.attrs;
+      }
+      metadata.extra = Some(serde_json::to_string(&token_extra).unwrap());
+      metadata.issued_at = previous.issued_at;
+
+      self.tokens.token_metadata_by_id.as_mut().unwrap().insert(token_id, &metadata);
+    }
+  }
+
+  fn internal_set_evolve_at(
+    &mut self,
+    token_id: &TokenId,
+    evolve_at: u64,
+  ) {
+    if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
+      let mut metadata = token_metadata_by_id.get(token_id).unwrap();
+      let mut token_extra = TokenExtra::parse(metadata.extra.as_ref().unwrap());
+      token_extra.evolve_at = evolve_at;
+      metadata.extra = Some(serde_json::to_string(&token_extra).unwrap());
+      token_metadata_by_id.insert(token_id, &metadata);
+    }
+  }
+
+  fn internal_transfer(
+    &mut self,
+    sender_id: &AccountId,
+    receiver_id: &AccountId,
+    token_id: &TokenId,
+    approval_id: Option<u64>,
+    memo: Option<String>,
+  ) -> (AccountId, Option<HashMap<AccountId, u64>>) {
+    self.assert_current_approval_id(sender_id, token_id, approval_id);
+    let (previous_owner_id, previous_approvals) =
+      self.internal_move_token(sender_id, receiver_id, token_id, approval_id);
+
+    let authorized_id = if sender_id != &previous_owner_id {
+      Some(sender_id.clone())
+    } else {
+      None
+    };
+
+    events::emit(EventLogVariant::NftTransfer(vec![NftTransferLog {
+      authorized_id,
+      old_owner_id: previous_owner_id.clone(),
+      new_owner_id: receiver_id.clone(),
+      token_ids: vec![token_id.clone()],
+      memo,
+    }]));
+
+    (previous_owner_id, previous_approvals)
+  }
+
+  /// Moves the token and every index keyed by its owner, without logging.
+  fn internal_move_token(
+    &mut self,
+    sender_id: &AccountId,
+    receiver_id: &AccountId,
+    token_id: &TokenId,
+    approval_id: Option<u64>,
+  ) -> (AccountId, Option<HashMap<AccountId, u64>>) {
+    self.assert_not_locked(token_id);
+
+    // The standard would create a missing receiver set under its own key;
+    // creating it here keeps every owner's set under `tokens_per_owner_key`.
+    if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
+      if !tokens_per_owner.contains_key(receiver_id) {
+        tokens_per_owner.insert(receiver_id, &UnorderedSet::new(tokens_per_owner_key(receiver_id)));
+      }
+    }
+
+    let (previous_owner_id, previous_approvals) =
+      self.tokens.internal_transfer(sender_id, receiver_id, token_id, approval_id, None);
+    self.co_owners_per_token.remove(token_id);
+    self.rescue_per_token.remove(token_id);
+
+    self.internal_remove_token_from_owner_count(&previous_owner_id);
+    self.internal_add_token_to_owner_count(receiver_id);
+
+    (previous_owner_id, previous_approvals)
+  }
+
+  /// An approved sender must quote the approval id it currently holds, so
+  /// an approval granted before an earlier transfer cannot be replayed, and
+  /// an expired approval counts as revoked. The owner may omit it.
+  fn assert_current_approval_id(
+    &self,
+    sender_id: &AccountId,
+    token_id: &TokenId,
+    approval_id: Option<u64>,
+  ) {
+    let owner_id = self.tokens.owner_by_id
+      .get(token_id)
+      .unwrap_or_else(|| panic!("{}", Error::TokenNotFound));
+    if sender_id == &owner_id {
+      return;
+    }
+
+    let current_id = self.tokens.approvals_by_id
+      .as_ref()
+      .and_then(|by_id| by_id.get(token_id))
+      .and_then(|approvals| approvals.get(sender_id).copied());
+    if let Some(current_id) = current_id {
+      assert!(!self.is_approval_expired(token_id, sender_id, current_id), "{}", Error::ApprovalExpired);
+      assert_eq!(approval_id, Some(current_id), "{}", Error::ApprovalIdMismatch);
+    }
+  }
+
+  fn internal_burn(
+    &mut self,
+    token_id: &TokenId,
+    owner_id: &AccountId,
+  ) {
+    self.assert_not_locked(token_id);
+    self.tokens.owner_by_id.remove(token_id);
+
+    if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
+      token_metadata_by_id.remove(token_id);
+    }
+
+    if let Some(next_approval_id_by_id) = &mut self.tokens.next_approval_id_by_id {
+      next_approval_id_by_id.remove(token_id);
+    }
+
+    if let Some(approvals_by_id) = &mut self.tokens.approvals_by_id {
+      approvals_by_id.remove(token_id);
+    }
+    self.approval_expiry_by_id.remove(token_id);
+    self.rescue_per_token.remove(token_id);
+    self.unrevealed_eggs.remove(token_id);
+
+    if let Some(mut token_set) = self.internal_owner_token_set(owner_id) {
+      token_set.remove(token_id);
+      self.internal_set_owner_token_set(owner_id, &token_set);
+    }
+    self.internal_remove_token_from_owner_count(owner_id);
+
+    if let Some(species) = self.egg_per_token_id.remove(token_id) {
+      self.internal_remove_token_from_species(species, token_id);
+
+      let species_supply = self.supply_per_species.get(&species).unwrap_or(0);
+      self.supply_per_species.insert(&species, &species_supply.saturating_sub(1));
+    }
+
+    if let Some(level) = self.level_per_token_id.remove(token_id) {
+      self.internal_remove_token_from_level(level, token_id);
+    }
+
+    self.xp_per_token_id.remove(token_id);
+    self.co_owners_per_token.remove(token_id);
+    self.nickname_per_token.remove(token_id);
+    self.branch_per_token.remove(token_id);
+  }
+
+  /// `from_index` past the end yields an empty page and `limit` is capped at
+  /// `MAX_PAGE_LIMIT`, which is also the default.
+  fn paginate_token_set(
+    &self,
+    token_set: &UnorderedSet<TokenId>,
+    from_index: Option<U128>,
+    limit: Option<u64>,
+  ) -> Vec<Token> {
+    self.paginate_token_ids(token_set, from_index, limit)
+      .into_iter()
+      .map(|token| self.nft_token(token).unwrap())
+      .collect()
+  }
+
+  /// Reads the page by index straight out of the set's backing vector, so a
+  /// page costs O(limit) storage reads wherever it starts; skipping through
+  /// an iterator would read every element before `from_index` as well.
+  fn paginate_token_ids(
+    &self,
+    token_set: &UnorderedSet<TokenId>,
+    from_index: Option<U128>,
+    limit: Option<u64>,
+  ) -> Vec<TokenId> {
+    let start = u128::from(from_index.unwrap_or(U128(0)));
+    let limit = limit.unwrap_or(MAX_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+    let len = token_set.len();
+    if limit == 0 || start >= len as u128 {
+      return vec![];
+    }
+
+    let start = start as u64;
+    let end = len.min(start + limit);
+    let token_ids = token_set.as_vector();
+    (start..end).map(|index| token_ids.get(index).unwrap()).collect()
+  }
+
+  fn owner_token_count(
+    &self,
+    account_id: &AccountId,
+  ) -> u64 {
+    self.count_per_owner.get(account_id).unwrap_or(0)
+  }
+
+  fn internal_add_token_to_owner_count(
+    &mut self,
+    account_id: &AccountId,
+  ) {
+    let count = self.owner_token_count(account_id);
+    self.count_per_owner.insert(account_id, &(count + 1));
+  }
+
+  fn internal_remove_token_from_owner_count(
+    &mut self,
+    account_id: &AccountId,
+  ) {
+    match self.owner_token_count(account_id) {
+      0 | 1 => {
+        self.count_per_owner.remove(account_id);
+      }
+      count => {
+        self.count_per_owner.insert(account_id, &(count - 1));
+      }
+    }
+  }
+
+  fn internal_add_token_to_species(
+    &mut self,
+    species: u64,
+    token_id: &TokenId,
+  ) {
+    let mut token_ids = self.tokens_per_species.get(&species).unwrap_or_else(|| {
+      UnorderedSet::new(StorageKey::TokensPerSpeciesInner { species })
+    });
+    token_ids.insert(token_id);
+    self.tokens_per_species.insert(&species, &token_ids);
+  }
+
+  fn internal_remove_token_from_species(
+    &mut self,
+    species: u64,
+    token_id: &TokenId,
+  ) {
+    if let Some(mut token_ids) = self.tokens_per_species.get(&species) {
+      token_ids.remove(token_id);
+      if token_ids.is_empty() {
+        self.tokens_per_species.remove(&species);
+      } else {
+        self.tokens_per_species.insert(&species, &token_ids);
+      }
+    }
+  }
+
+  fn internal_add_token_to_level(
+    &mut self,
+    level: u64,
+    token_id: &TokenId,
+  ) {
+    let mut token_ids = self.tokens_per_level.get(&level).unwrap_or_else(|| {
+      UnorderedSet::new(StorageKey::TokensPerLevelInner { level })
+    });
+    if token_ids.insert(token_id) {
+      let supply = self.supply_per_level.get(&level).unwrap_or(0);
+      self.supply_per_level.insert(&level, &(supply + 1));
+    }
+    self.tokens_per_level.insert(&level, &token_ids);
+  }
+
+  fn internal_remove_token_from_level(
+    &mut self,
+    level: u64,
+    token_id: &TokenId,
+  ) {
+    if let Some(mut token_ids) = self.tokens_per_level.get(&level) {
+      if token_ids.remove(token_id) {
+        match self.supply_per_level.get(&level).unwrap_or(0) {
+          0 | 1 => {
+            self.supply_per_level.remove(&level);
+          }
+          supply => {
+            self.supply_per_level.insert(&level, &(supply - 1));
+          }
+        }
+      }
+      if token_ids.is_empty() {
+        self.tokens_per_level.remove(&level);
+      } else {
+        self.tokens_per_level.insert(&level, &token_ids);
+      }
+    }
+  }
+
+  /// The token's current metadata must come from a template of its stored
+  /// level, either a registered `monster_{level}` one or the built-in stage,
+  /// otherwise evolving would skip or repeat a stage.
+  fn assert_metadata_matches_level(
+    &self,
+    token_id: &TokenId,
+  ) {
+    let level = self.level_per_token_id.get(token_id).unwrap();
+    let species = self.egg_per_token_id.get(token_id).unwrap();
+    let title = self.tokens.token_metadata_by_id
+      .as_ref()
+      .and_then(|by_id| by_id.get(token_id))
+      .and_then(|metadata| metadata.title);
+
+    let matches_registered = self.metadata_per_type
+      .get(&format!("monster_{}", level))
+      .map(|templates| templates.iter().any(|template| template.title == title))
+      .unwrap_or(false);
+    let matches_built_in = get_built_in_metadata(level, species)
+      .map(|template| template.title == title)
+      .unwrap_or(false);
+    let matches_branch = self.evolve_branches
+      .get(&(species, level))
+      .map(|forms| forms.iter().any(|form| form.title == title))
+      .unwrap_or(false);
+
+    assert!(
+      matches_registered || matches_built_in || matches_branch,
+      "{}",
+      Error::MetadataLevelMismatch,
+    );
+  }
+
+  fn update_contract_metadata<F: FnOnce(&mut NFTContractMetadata)>(
+    &mut self,
+    memo: String,
+    update: F,
+  ) {
+    self.assert_metadata_not_frozen();
+    let mut metadata = self.metadata.get().unwrap();
+    update(&mut metadata);
+    metadata.assert_valid();
+    self.metadata.set(&metadata);
+
+    events::emit(EventLogVariant::ContractMetadataUpdate(vec![ContractMetadataUpdateLog {
+      memo: Some(memo),
+    }]));
+  }
+
+  fn has_template_for_level(
+    &self,
+    level: u64,
+    species: u64,
+  ) -> bool {
+    self.resolve_template(level, species).is_some()
+  }
+
+  fn assert_metadata_not_frozen(&self) {
+    assert!(!self.metadata_frozen, "{}", Error::MetadataFrozen);
+  }
+
+  fn assert_not_locked(
+    &self,
+    token_id: &TokenId,
+  ) {
+    assert!(self.locked_tokens.get(token_id).is_none(), "{}", Error::TokenLocked);
+  }
+
+  fn assert_owner(&self) {
+    assert_eq!(
+      env::predecessor_account_id(),
+      self.owner_id,
+      "{}",
+      Error::Unauthorized,
+    );
+  }
+
+  /// Advances the counter and returns the new id as a `TokenId`.
+  fn increment_token_id(
+    &mut self,
+  ) -> TokenId {
+    self.current_token_id += 1;
+    self.current_token_id.to_string()
+  }
+
+  /// `copies` is the number of tokens minted from the (level, species)
+  /// template so far, this one included; burns do not lower it.
+  fn get_metadata_per_type(
+    &mut self,
+    metadata_type: u64,
+    metadata_set: u64,
+  ) -> TokenMetadata {
+    let metadata = self.resolve_template(metadata_type, metadata_set)
+      .unwrap_or_else(|| panic!("{}", Error::MaxLevelReached));
+
+    self.stamp_metadata(metadata, metadata_type, metadata_set)
+  }
+
+  fn has_registered_template(
+    &self,
+    level: u64,
+    species: u64,
+  ) -> bool {
+    self.registered_template(level, species).is_some()
+  }
+
+  /// The template `add_metadata` registered for `species` at `level`: the
+  /// `species`-th one added as `monster_{level}`, never wrapping around.
+  fn registered_template(
+    &self,
+    level: u64,
+    species: u64,
+  ) -> Option<TokenMetadata> {
+    self.metadata_per_type
+      .get(&format!("monster_{}", level))
+      .filter(|templates| species >= 1 && species <= templates.len())
+      .and_then(|templates| templates.as_vector().get(species - 1))
+  }
+
+  /// Registered templates take precedence over the built-in stages.
+  fn resolve_template(
+    &self,
+    metadata_type: u64,
+    metadata_set: u64,
+  ) -> Option<TokenMetadata> {
+    self.registered_template(metadata_type, metadata_set)
+      .or_else(|| get_built_in_metadata(metadata_type, metadata_set))
+  }
+
+  /// Turns a template into a token's metadata: copy count, issue time,
+  /// renderer media and the `TokenExtra` cooldown.
+  fn stamp_metadata(
+    &mut self,
+    metadata: TokenMetadata,
+    metadata_type: u64,
+    metadata_set: u64,
+  ) -> TokenMetadata {
+    let template_key = (metadata_type, metadata_set);
+    let copies = self.copies_per_template.get(&template_key).unwrap_or(0) + 1;
+    self.copies_per_template.insert(&template_key, &copies);
+
+    self.render_metadata(metadata, metadata_type, metadata_set, copies)
+  }
+
+  fn render_metadata(
+    &self,
+    mut metadata: TokenMetadata,
+    metadata_type: u64,
+    metadata_set: u64,
+    copies: u64,
+  ) -> TokenMetadata {
+    let current = env::block_timestamp() / 1000000;
+    let next = current.saturating_add(self.cooldown_for_level(metadata_type).0);
+
+    let attrs = metadata.extra.as_ref().map(|extra| {
+      serde_json::from_str(extra).unwrap_or_else(|_| Value::String(extra.clone()))
+    });
+
+    if let Some(renderer_base) = &self.renderer_base {
+      metadata.media = Some(format!("{}/{}/{}", renderer_base, metadata_set, metadata_type));
+      metadata.media_hash = None;
+    } else if metadata.media.is_none() {
+      metadata.media = self.default_media.clone();
+    }
+
+    metadata.issued_at = Some(current.to_string());
+    metadata.copies = Some(copies);
+    metadata.extra = Some(serde_json::to_string(&TokenExtra { evolve_at: next, attrs }).unwrap());
+
+    metadata
+  }
+
+  /// Rolls `0..upper` with `random::in_range`, which reads up to the first
+  /// eight seed bytes big-endian, so weight totals above 256 still get an
+  /// even roll.
+  fn get_random_number(
+    &self,
+    random_source: &dyn RandomSource,
+    upper: u64,
+  ) -> u64 {
+    let seed = random_source.random_seed();
+    assert!(!seed.is_empty(), "{}", Error::EmptyRandomSeed);
+
+    random::in_range(&seed, 0, upper)
+  }
+
+}
+
+#[near_bindgen]
+impl NonFungibleTokenMetadataProvider for Contract {
+    fn nft_metadata(&self) -> NFTContractMetadata {
+        self.metadata.get().unwrap()
+    }
+}
+
+fn get_built_in_metadata(level: u64, species: u64) -> Option<TokenMetadata> {
+  match level {
+    0 => Some(metadatas::get_metadata_egg(species)),
+    1 => Some(metadatas::get_metadata_monster_1(species)),
+    2 => Some(metadatas::get_metadata_monster_2(species)),
+    3 => Some(metadatas::get_metadata_monster_3(species)),
+    _ => None,
+  }
+}
+
+/// The one place an owner's per-owner set key is derived. The account id is
+/// validated first so malformed bytes never end up hashed into a key.
+fn tokens_per_owner_key(owner_id: &AccountId) -> StorageKey {
+  assert!(env::is_valid_account_id(owner_id.as_bytes()), "{}", Error::InvalidReceiver);
+  StorageKey::TokensPerOwner {
+    account_hash: env::sha256(owner_id.as_bytes()),
+  }
+}
+
+/// Maps the accepted spellings of a template type (`egg`, `monster_{level}`
+/// or a bare level) onto the `monster_{level}` key the resolver reads.
+fn canonical_metadata_type(metadata_type: &str) -> Option<String> {
+  let lower_type = metadata_type.trim().to_lowercase();
+  let level = if lower_type == "egg" {
+    "0"
+  } else {
+    lower_type.strip_prefix("monster_").unwrap_or(&lower_type)
+  };
+  if level.is_empty() || !level.bytes().all(|b| b.is_ascii_digit()) {
+    return None;
+  }
+  level.parse::<u64>().ok().map(|level| format!("monster_{}", level))
+}
+
+fn is_valid_uri(uri: &str) -> bool {
+  ["https://", "http://", "ipfs://"].iter().any(|scheme| {
+    uri.starts_with(scheme) && uri.len() > scheme.len()
+  })
+}
+
+/// Memos ride along in event logs only; the cap keeps callers from
+/// stuffing arbitrary payloads into them.
+fn assert_memo_len(memo: &Option<String>) {
+  if let Some(memo) = memo {
+    assert!(memo.len() <= MAX_MEMO_LEN, "{}", Error::MemoTooLong(MAX_MEMO_LEN));
+  }
+}
+
+fn assert_batch_size(len: usize, max: usize) {
+  assert!(len >= 1 && len <= max, "{}", Error::InvalidBatchSize(max));
+}
+
+fn refund_deposit(required_cost: Balance) {
+  let refund = env::attached_deposit()
+    .checked_sub(required_cost)
+    .unwrap_or_else(|| panic!("{}", Error::InsufficientDeposit(required_cost)));
+
+  if refund > REFUND_DUST_THRESHOLD {
+    Promise::new(env::predecessor_account_id()).transfer(refund);
+  }
+}
+
+fn refund_storage(account_id: AccountId, storage_freed: StorageUsage) {
+  let refund = env::storage_byte_cost() * Balance::from(storage_freed);
+
+  if refund > 0 {
+    Promise::new(account_id).transfer(refund);
+  }
+}
+
+/// Debug-only views for migration tooling, compiled out of release wasm.
+#[cfg(feature = "debug")]
+#[near_bindgen]
+impl Contract {
+  /// `"{variant} = 0x{tag}"` per `StorageKey` variant. Collections created
+  /// from a variant with fields prefix their keys with the tag followed by
+  /// the borsh-serialized fields.
+  pub fn storage_layout(&self) -> Vec<String> {
+    StorageKey::layout()
+      .into_iter()
+      .map(|(name, key)| format!("{} = 0x{:02x}", name, key.try_to_vec().unwrap()[0]))
+      .collect()
+  }
+}
+
+/// Cross-checks every derived index and counter against `owner_by_id`, the
+/// ground truth for which tokens exist and who holds them.
+#[cfg(all(test, not(target_arch = "wasm32")))]
+impl Contract {
+  fn assert_invariants(&self) {
+    let total_supply = self.tokens.owner_by_id.len();
+
+    for (token_id, owner_id) in self.tokens.owner_by_id.iter() {
+      let species = self.egg_per_token_id.get(&token_id).expect("token without species");
+      let level = self.level_per_token_id.get(&token_id).expect("token without level");
+      assert!(self.tokens_per_species.get(&species).map_or(false, |set| set.contains(&token_id)));
+      assert!(self.tokens_per_level.get(&level).map_or(false, |set| set.contains(&token_id)));
+      self.tokens.tokens_per_owner.as_ref().and_then(|by_owner| by_owner.get(&owner_id)).expect("owner without token set");
+      let token_set = UnorderedSet::<TokenId>::new(tokens_per_owner_key(&owner_id));
+      assert!(token_set.contains(&token_id), "{} missing from {}'s set", token_id, owner_id);
+    }
+
+    assert_eq!(self.egg_per_token_id.len(), total_supply);
+    assert_eq!(self.level_per_token_id.len(), total_supply);
+    assert_eq!(self.tokens_per_species.values().map(|set| set.len()).sum::<u64>(), total_supply);
+    assert_eq!(self.supply_per_species.values().sum::<u64>(), total_supply);
+    assert_eq!(self.tokens_per_level.values().map(|set| set.len()).sum::<u64>(), total_supply);
+    for (level, supply) in self.supply_per_level.iter() {
+      assert_eq!(self.tokens_per_level.get(&level).map_or(0, |set| set.len()), supply);
+    }
+
+    assert_eq!(self.count_per_owner.values().sum::<u64>(), total_supply);
+    for (owner_id, count) in self.count_per_owner.iter() {
+      let token_set = self.tokens.tokens_per_owner
+        .as_ref()
+        .and_then(|by_owner| by_owner.get(&owner_id))
+        .expect("owner without token set");
+      assert_eq!(token_set.len(), count, "{}'s count drifted", owner_id);
+    }
+  }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{
+        accounts, get_created_receipts, get_logs, testing_env_with_promise_results, VMContextBuilder,
+    };
+    use near_sdk::MockedBlockchain;
+    use near_sdk::{testing_env};
+
+    // The default evolve fee plus headroom for the storage an evolve adds;
+    // the excess is refunded.
+    const EVOLVE_DEPOSIT: Balance = 2 * MINIMUM_EARLY_DEPOSIT;
+
+    const DATA_IMAGE_SVG_PARAS_ICON: &str = "data:image/svg+xml,%3Csvg width='1080' height='1080' viewBox='0 0 1080 1080' fill='none' xmlns='http://www.w3.org/2000/svg'%3E%3Crect width='1080' height='1080' rx='10' fill='%230000BA'/%3E%3Cpath fill-rule='evenodd' clip-rule='evenodd' d='M335.238 896.881L240 184L642.381 255.288C659.486 259.781 675.323 263.392 689.906 266.718C744.744 279.224 781.843 287.684 801.905 323.725C827.302 369.032 840 424.795 840 491.014C840 557.55 827.302 613.471 801.905 658.779C776.508 704.087 723.333 726.74 642.381 726.74H468.095L501.429 896.881H335.238ZM387.619 331.329L604.777 369.407C614.008 371.807 622.555 373.736 630.426 375.513C660.02 382.193 680.042 386.712 690.869 405.963C704.575 430.164 711.428 459.95 711.428 495.321C711.428 530.861 704.575 560.731 690.869 584.932C677.163 609.133 648.466 621.234 604.777 621.234H505.578L445.798 616.481L387.619 331.329Z' fill='white'/%3E%3C/svg%3E";
+
+    fn get_context(predecessor_account_id: ValidAccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id);
+        builder
+    }
+
+    fn setup_contract() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let contract = Contract::new_default_meta(accounts(0));
+        (context, contract)
+    }
+
+    fn sample_token_metadata() -> TokenMetadata {
+      TokenMetadata {
+        title: Some("Olympus Mons".into()),
+        description: Some("The tallest mountain in the charted solar system".into()),
+        media: None,
+        media_hash: None,
+        copies: Some(1u64),
+        issued_at: None,
+        expires_at: None,
+        starts_at: None,
+        updated_at: None,
+        extra: None,
+        reference: None,
+        reference_hash: None,
+      }
+    }
+
+    #[test]
+    fn test_new() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let contract = Contract::new(
+            accounts(1),
+            NFTContractMetadata {
+                spec: NFT_METADATA_SPEC.to_string(),
+                name: "Triple Triad".to_string(),
+                symbol: "TRIAD".to_string(),
+                icon: Some(DATA_IMAGE_SVG_PARAS_ICON.to_string()),
+                base_uri: Some("https://ipfs.fleek.co/ipfs/".to_string()),
+                reference: None,
+                reference_hash: None,
+            },
+            None,
+        );
+        testing_env!(context.is_view(true).build());
+        assert_eq!(contract.get_owner(), accounts(1).to_string());
+        assert_eq!(contract.nft_metadata().base_uri.unwrap(), "https://ipfs.fleek.co/ipfs/".to_string());
+        assert_eq!(contract.nft_metadata().icon.unwrap(), DATA_IMAGE_SVG_PARAS_ICON.to_string());
+    }
+
+    #[test]
+    fn test_renderer_base_sets_media() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_renderer_base(Some("https://render.nearmon.io/".to_string()));
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(MINIMUM_EARLY_DEPOSIT).build());
+        let egg_id = contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![2]));
+        assert_eq!(
+            contract.token_metadata(egg_id.clone()).unwrap().media,
+            Some("https://render.nearmon.io/3/0".to_string())
+        );
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), egg_id);
+        assert_eq!(
+            contract.token_metadata(evolved_id).unwrap().media,
+            Some("https://render.nearmon.io/3/1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_media_falls_back_without_renderer() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(MINIMUM_EARLY_DEPOSIT).build());
+        let egg_id = contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![2]));
+
+        assert_eq!(
+            contract.token_metadata(egg_id).unwrap().media,
+            get_built_in_metadata(0, 3).unwrap().media
+        );
+    }
+
+    #[test]
+    fn test_reroll_egg_changes_species() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(MINIMUM_EARLY_DEPOSIT).build());
+        let egg_id = contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![0]));
+        let evolve_at = token_extra_of(&contract, egg_id.clone()).evolve_at;
+
+        testing_env!(context.block_timestamp(1_000_000 * 1000000).build());
+        let species = contract.internal_reroll_egg(&egg_id, &FixedRandomSource(vec![4]));
+
+        assert_eq!(species, 5);
+        assert_eq!(contract.egg_per_token_id.get(&egg_id), Some(5));
+        assert_eq!(contract.supply_for_species(1), U128(0));
+        assert_eq!(contract.supply_for_species(5), U128(1));
+        assert_eq!(contract.nft_tokens_by_species(5, None, None)[0].token_id, egg_id);
+        assert_eq!(
+            contract.token_metadata(egg_id.clone()).unwrap().title,
+            get_built_in_metadata(0, 5).unwrap().title
+        );
+        assert_eq!(token_extra_of(&contract, egg_id).evolve_at, evolve_at);
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_reroll_egg_charges_fee() {
+        let (mut context, mut contract) = setup_contract();
+        let egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(2 * MINIMUM_EARLY_DEPOSIT).build());
+        let species = contract.reroll_egg(egg_id.clone());
+        assert_eq!(contract.egg_per_token_id.get(&egg_id), Some(species));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only unhatched eggs can be rerolled")]
+    fn test_reroll_rejects_evolved_monster() {
+        let (mut context, mut contract) = setup_contract();
+        let egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), egg_id);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(2 * MINIMUM_EARLY_DEPOSIT).build());
+        contract.reroll_egg(evolved_id);
+    }
+
+    #[test]
+    fn test_admin_reset_evolve_timer_allows_evolve() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let evolve_at = token_extra_of(&contract, token_id.clone()).evolve_at;
+        let now = evolve_at - 1;
+
+        testing_env!(context.predecessor_account_id(accounts(0)).block_timestamp(now * 1000000).build());
+        contract.admin_reset_evolve_timer(token_id.clone());
+        assert_eq!(token_extra_of(&contract, token_id.clone()).evolve_at, now);
+        assert!(get_logs().iter().any(|log| log.contains("\"event\":\"nft_metadata_update\"")));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(EVOLVE_DEPOSIT)
+            .build());
+        contract.nft_evolve(token_id, None, None, None);
+        assert_eq!(contract.level_per_token(contract.current_token_id.to_string()), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Token not found")]
+    fn test_admin_reset_evolve_timer_missing_token() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.admin_reset_evolve_timer("404".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn test_admin_reset_evolve_timer_requires_owner() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.admin_reset_evolve_timer(token_id);
+    }
+
+    #[test]
+    fn test_regenerate_metadata_applies_new_template() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let before = contract.nft_token(token_id.clone()).unwrap().metadata.unwrap();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .block_timestamp(env::block_timestamp() + 1_000_000_000)
+            .build());
+        let mut template = sample_token_metadata();
+        template.title = Some("Golden Egg".to_string());
+        contract.add_metadata("egg".to_string(), template);
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.regenerate_metadata(token_id.clone());
+
+        let after = contract.nft_token(token_id.clone()).unwrap().metadata.unwrap();
+        assert_eq!(after.title, Some("Golden Egg".to_string()));
+        assert_eq!(after.issued_at, before.issued_at);
+        assert_eq!(after.copies, before.copies);
+        assert_eq!(
+            token_extra_of(&contract, token_id.clone()).evolve_at,
+            TokenExtra::parse(before.extra.as_ref().unwrap()).evolve_at
+        );
+
+        let logs = get_logs();
+        let event: Value = serde_json::from_str(logs[0].strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+        assert_eq!(event["event"], "nft_metadata_update");
+        assert_eq!(event["data"][0]["token_ids"], serde_json::json!([token_id]));
+    }
+
+    #[test]
+    fn test_regenerate_metadata_range_pages_through_tokens() {
+        let (mut context, mut contract) = setup_contract();
+        let first = mint_egg(&mut context, &mut contract, accounts(1));
+        let second = mint_egg(&mut context, &mut contract, accounts(2));
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut template = sample_token_metadata();
+        template.title = Some("Golden Egg".to_string());
+        contract.add_metadata("egg".to_string(), template);
+
+        contract.regenerate_metadata_range(Some(U128(1)), Some(1));
+        let title_of = |token_id: TokenId| contract.nft_token(token_id).unwrap().metadata.unwrap().title;
+        assert_ne!(title_of(first), Some("Golden Egg".to_string()));
+        assert_eq!(title_of(second), Some("Golden Egg".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn test_regenerate_metadata_requires_owner() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.regenerate_metadata(token_id);
+    }
+
+    #[test]
+    fn test_config_matches_individual_getters() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_evolve_fee(U128(7));
+        contract.set_reroll_fee(U128(3));
+        contract.lower_max_supply(500);
+        contract.set_max_mints_per_account(Some(4));
+        contract.set_sale_window(Some(1_000), Some(2_000));
+        contract.set_treasury(Some(accounts(4)), 1_500);
+        contract.freeze_metadata();
+
+        let config = contract.get_config();
+        assert_eq!(config.owner_id, contract.get_owner());
+        assert_eq!(config.mint_fee, U128(MINIMUM_EARLY_DEPOSIT));
+        assert_eq!(config.evolve_fee, contract.get_evolve_fee());
+        assert_eq!(config.reroll_fee, contract.get_reroll_fee());
+        assert_eq!(config.max_supply, contract.get_max_supply());
+        assert_eq!(config.max_mints_per_account, contract.get_max_mints_per_account());
+        assert_eq!((config.sale_start_ms, config.sale_end_ms), (Some(U64(1_000)), Some(U64(2_000))));
+        assert_eq!(config.species_count, contract.get_species_weights().len() as u64);
+        assert_eq!(config.metadata_frozen, contract.is_metadata_frozen());
+        assert_eq!(config.renderer_base, contract.get_renderer_base());
+        assert_eq!(
+            config.treasury.map(|treasury| (treasury, config.treasury_bps)),
+            contract.get_treasury()
+        );
+    }
+
+    #[test]
+    fn test_bootstrap_matches_individual_getters() {
+        let (mut context, mut contract) = setup_contract();
+        mint_egg(&mut context, &mut contract, accounts(1));
+        mint_egg(&mut context, &mut contract, accounts(2));
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_max_level_for_species(3, Some(2));
+
+        let bootstrap = contract.bootstrap();
+        assert_eq!(
+            serde_json::to_value(&bootstrap.metadata).unwrap(),
+            serde_json::to_value(contract.nft_metadata()).unwrap()
+        );
+        assert_eq!(bootstrap.owner_id, contract.get_owner());
+        assert_eq!(bootstrap.config, contract.get_config());
+        assert_eq!(bootstrap.total_supply, contract.nft_total_supply());
+        assert_eq!(bootstrap.total_supply, U128(2));
+        assert_eq!(bootstrap.species, contract.species_catalog());
+        assert_eq!(bootstrap.species.len() as u64, SPECIES_COUNT);
+        assert_eq!(bootstrap.species[2].max_level, Some(2));
+        assert_eq!(
+            bootstrap.species.iter().map(|entry| entry.supply.0).sum::<u128>(),
+            bootstrap.total_supply.0
+        );
+    }
+
+    #[test]
+    fn test_cooldown_grows_geometrically_per_level() {
+        let (mut context, mut contract) = setup_contract();
+        assert_eq!(contract.cooldown_for_level(3), U64(EVOLVE_COOLDOWN_MS));
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_cooldown_formula(Some(1_000), 20_000);
+        assert_eq!(contract.cooldown_for_level(0), U64(1_000));
+        assert_eq!(contract.cooldown_for_level(1), U64(2_000));
+        assert_eq!(contract.cooldown_for_level(3), U64(8_000));
+
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let minted_at = env::block_timestamp() / 1000000;
+        assert_eq!(token_extra_of(&contract, token_id.clone()).evolve_at, minted_at + 1_000);
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), token_id);
+        let evolved_at = env::block_timestamp() / 1000000;
+        assert_eq!(token_extra_of(&contract, evolved_id).evolve_at, evolved_at + 2_000);
+    }
+
+    #[test]
+    fn test_cooldown_saturates_instead_of_overflowing() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_cooldown_formula(Some(u64::MAX / 2), 30_000);
+        assert_eq!(contract.cooldown_for_level(1), U64(u64::MAX));
+        assert_eq!(contract.cooldown_for_level(1_000), U64(u64::MAX));
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_cooldown_formula(Some(u64::MAX), 30_000);
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        assert_eq!(token_extra_of(&contract, token_id).evolve_at, u64::MAX);
+    }
+
+    #[test]
+    fn test_mint_and_evolve_memos_reach_events() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_mint_egg(accounts(1).to_string(), Some("spring-campaign".to_string()));
+        let token_id = contract.current_token_id.to_string();
+        let event: Value = serde_json::from_str(get_logs()[0].strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+        assert_eq!(event["event"], "nft_mint");
+        assert_eq!(event["data"][0]["memo"], "spring-campaign");
+
+        let evolve_time = token_extra_of(&contract, token_id.clone()).evolve_at;
+        testing_env!(context
+            .attached_deposit(EVOLVE_DEPOSIT)
+            .block_timestamp(evolve_time * 1000000)
+            .build());
+        contract.nft_evolve(token_id, None, None, Some("quest-funnel".to_string()));
+        let logs = get_logs();
+        assert_eq!(logs.len(), 2);
+        assert!(logs.iter().all(|log| log.contains("\"memo\":\"quest-funnel\"")));
+    }
+
+    #[test]
+    #[should_panic(expected = "Memo cannot exceed 256 bytes")]
+    fn test_over_long_memo_rejected() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_mint_egg(accounts(1).to_string(), Some("x".repeat(MAX_MEMO_LEN + 1)));
+    }
+
+    #[cfg(feature = "debug")]
+    #[test]
+    fn test_storage_layout_lists_variant_prefixes() {
+        let (_, contract) = setup_contract();
+        let layout = contract.storage_layout();
+        assert_eq!(layout.len(), 38);
+        assert_eq!(layout[0], "NonFungibleToken = 0x00");
+        assert_eq!(layout[4], "TokensPerOwner { account_hash: .. } = 0x04");
+        assert_eq!(layout[28], "SupplyPerLevel = 0x1c");
+        assert_eq!(layout[31], "RedemptionPerLevel = 0x1f");
+    }
+
+    #[test]
+    fn test_default_icon_fills_missing_media() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_default_metadata_icon(Some("https://media.nearmon.example/missing.png".to_string()));
+        contract.add_metadata("egg".to_string(), sample_token_metadata());
+        let mut illustrated = sample_token_metadata();
+        illustrated.media = Some("ipfs://egg-2".to_string());
+        contract.add_metadata("egg".to_string(), illustrated);
+
+        testing_env!(context.attached_deposit(2 * MINIMUM_EARLY_DEPOSIT).build());
+        let plain_id = contract.nft_mint_egg_species(accounts(1).to_string(), 1);
+        let illustrated_id = contract.nft_mint_egg_species(accounts(1).to_string(), 2);
+
+        let media_of = |token_id: TokenId| contract.nft_token(token_id).unwrap().metadata.unwrap().media;
+        assert_eq!(media_of(plain_id), Some("https://media.nearmon.example/missing.png".to_string()));
+        assert_eq!(media_of(illustrated_id), Some("ipfs://egg-2".to_string()));
+    }
+
+    #[test]
+    fn test_next_token_id_predicts_next_mint() {
+        let (mut context, mut contract) = setup_contract();
+        assert_eq!(contract.next_token_id(), "1".to_string());
+
+        for _ in 0..3 {
+            let predicted = contract.next_token_id();
+            assert_eq!(mint_egg(&mut context, &mut contract, accounts(1)), predicted);
+        }
+        assert_eq!(contract.next_token_id(), "4".to_string());
+    }
+
+    #[test]
+    fn test_overpaid_mint_for_another_account_refunds_the_payer() {
+        let (mut context, mut contract) = setup_contract();
+        let overpayment = MINIMUM_EARLY_DEPOSIT / 2;
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT + overpayment)
+            .build());
+        contract.nft_mint_egg(accounts(2).to_string(), None);
+
+        assert_eq!(contract.nft_supply_for_owner(accounts(2).to_string()), U128(1));
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(receipts.contains(&format!("\"receiver_id\":\"{}\"", accounts(1))));
+        assert!(!receipts.contains(&format!("\"receiver_id\":\"{}\"", accounts(2))));
+        assert!(receipts.contains(&format!("\"Transfer\":{{\"deposit\":{}}}", overpayment)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Must attach 1000000000000000000000000 yoctoNEAR to cover storage")]
+    fn test_short_deposit_mint_fails_clearly() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT - 1)
+            .build());
+        contract.nft_mint_egg(accounts(1).to_string(), None);
+    }
+
+    #[test]
+    fn test_mint_cost_estimate_covers_mint() {
+        let (mut context, mut contract) = setup_contract();
+        let estimate = contract.mint_cost_estimate().0;
+        assert!(estimate >= MINIMUM_EARLY_DEPOSIT);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(estimate).build());
+        let storage_before = env::storage_usage();
+        contract.nft_mint_egg(accounts(1).to_string(), None);
+        let storage_used = env::storage_usage() - storage_before;
+
+        assert!(storage_used <= EGG_STORAGE_ESTIMATE_BYTES);
+        assert!(env::storage_byte_cost() * Balance::from(storage_used) <= estimate);
+    }
+
+    #[test]
+    fn test_mint_draws_from_storage_credit() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(2 * MINIMUM_EARLY_DEPOSIT).build());
+        assert_eq!(contract.deposit_storage(), U128(2 * MINIMUM_EARLY_DEPOSIT));
+
+        testing_env!(context.attached_deposit(0).build());
+        contract.nft_mint_egg(accounts(1).to_string(), None);
+        assert_eq!(contract.storage_credit_of(accounts(1).to_string()), U128(MINIMUM_EARLY_DEPOSIT));
+        assert!(get_created_receipts().is_empty());
+
+        contract.nft_mint_egg(accounts(1).to_string(), None);
+        assert_eq!(contract.storage_credit_of(accounts(1).to_string()), U128(0));
+        assert_eq!(contract.token_ids_for_owner(accounts(1).to_string(), None, None).len(), 2);
+    }
+
+    #[test]
+    fn test_partial_credit_tops_up_from_deposit() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(MINIMUM_EARLY_DEPOSIT / 2).build());
+        contract.deposit_storage();
+
+        testing_env!(context.attached_deposit(MINIMUM_EARLY_DEPOSIT / 2).build());
+        contract.nft_mint_egg(accounts(1).to_string(), None);
+        assert_eq!(contract.storage_credit_of(accounts(1).to_string()), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Must attach")]
+    fn test_mint_without_credit_requires_deposit() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(0).build());
+        contract.nft_mint_egg(accounts(1).to_string(), None);
+    }
+
+    #[test]
+    fn test_withdraw_storage_credit() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(MINIMUM_EARLY_DEPOSIT).build());
+        contract.deposit_storage();
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.withdraw_storage_credit(U128(MINIMUM_EARLY_DEPOSIT / 4));
+        assert_eq!(contract.storage_credit_of(accounts(1).to_string()), U128(MINIMUM_EARLY_DEPOSIT * 3 / 4));
+
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(receipts.contains(&format!("\"deposit\":{}", MINIMUM_EARLY_DEPOSIT / 4)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Storage credit of 0 yoctoNEAR is too low")]
+    fn test_withdraw_more_than_credit_fails() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        contract.withdraw_storage_credit(U128(1));
+    }
+
+    #[test]
+    fn test_set_base_uri() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+
+        contract.set_base_uri("https://media.nearmon.example/ipfs/".to_string());
+
+        assert_eq!(contract.nft_metadata().base_uri.unwrap(), "https://media.nearmon.example/ipfs/".to_string());
+        let logs = get_logs();
+        assert!(logs[0].contains("\"event\":\"contract_metadata_update\""));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid base_uri")]
+    fn test_set_base_uri_rejects_non_url() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+
+        contract.set_base_uri("not a url".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn test_set_base_uri_owner_only() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+
+        contract.set_base_uri("https://media.nearmon.example/".to_string());
+    }
+
+    #[test]
+    fn test_set_contract_name_symbol_and_icon() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+
+        contract.set_contract_name("Nearmon Reborn".to_string());
+        contract.set_contract_symbol("NMR".to_string());
+        contract.set_contract_icon(Some(DATA_IMAGE_SVG_PARAS_ICON.to_string()));
+
+        let metadata = contract.nft_metadata();
+        assert_eq!(metadata.name, "Nearmon Reborn".to_string());
+        assert_eq!(metadata.symbol, "NMR".to_string());
+        assert_eq!(metadata.icon.unwrap(), DATA_IMAGE_SVG_PARAS_ICON.to_string());
+        assert_eq!(metadata.spec, NFT_METADATA_SPEC.to_string());
+
+        contract.set_contract_icon(None);
+        assert!(contract.nft_metadata().icon.is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Symbol cannot be empty")]
+    fn test_set_contract_symbol_rejects_empty() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+
+        contract.set_contract_symbol(" ".to_string());
+    }
+
+    fn frozen_contract() -> (VMContextBuilder, Contract) {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        assert!(!contract.is_metadata_frozen());
+        contract.freeze_metadata();
+        assert!(contract.is_metadata_frozen());
+        (context, contract)
+    }
+
+    #[test]
+    #[should_panic(expected = "Metadata is frozen")]
+    fn test_freeze_blocks_add_metadata() {
+        let (_, mut contract) = frozen_contract();
+        contract.add_metadata("monster_1".to_string(), sample_token_metadata());
+    }
+
+    #[test]
+    #[should_panic(expected = "Metadata is frozen")]
+    fn test_freeze_blocks_set_base_uri() {
+        let (_, mut contract) = frozen_contract();
+        contract.set_base_uri("https://media.nearmon.example/".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Metadata is frozen")]
+    fn test_freeze_blocks_contract_metadata_setters() {
+        let (_, mut contract) = frozen_contract();
+        contract.set_contract_name("Nearmon Reborn".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Metadata is frozen")]
+    fn test_freeze_blocks_evolve_branches() {
+        let (_, mut contract) = frozen_contract();
+        contract.set_evolve_branches(1, 1, vec![sample_token_metadata()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Metadata is frozen")]
+    fn test_freeze_survives_a_second_freeze() {
+        let (_, mut contract) = frozen_contract();
+        contract.freeze_metadata();
+        contract.set_renderer_base(None);
+    }
+
+    fn mint_egg(context: &mut VMContextBuilder, contract: &mut Contract, receiver_id: ValidAccountId) -> TokenId {
+        testing_env!(context
+            .predecessor_account_id(receiver_id.clone())
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_mint_egg(receiver_id.to_string(), None);
+        contract.current_token_id.to_string()
+    }
+
+    fn token_extra_of(contract: &Contract, token_id: TokenId) -> TokenExtra {
+        TokenExtra::parse(&contract.nft_token(token_id).unwrap().metadata.unwrap().extra.unwrap())
+    }
+
+    fn evolve(context: &mut VMContextBuilder, contract: &mut Contract, owner_id: ValidAccountId, token_id: TokenId) -> TokenId {
+        let evolve_time = token_extra_of(contract, token_id.clone()).evolve_at;
+        testing_env!(context
+            .predecessor_account_id(owner_id.clone())
+            .attached_deposit(EVOLVE_DEPOSIT)
+            .block_timestamp(evolve_time * 1000000)
+            .build());
+        contract.nft_evolve(token_id, None, None, None);
+        contract.current_token_id.to_string()
+    }
+
+    struct FixedRandomSource(Vec<u8>);
+
+    impl RandomSource for FixedRandomSource {
+        fn random_seed(&self) -> Vec<u8> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test_mint_egg_with_injected_seed() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+
+        contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![5]));
+
+        let token_id = contract.current_token_id.to_string();
+        assert_eq!(contract.egg_per_token_id.get(&token_id).unwrap(), 6);
+        let metadata = contract.nft_token(token_id).unwrap().metadata.unwrap();
+        assert_eq!(metadata.title.unwrap(), "Egg #6".to_string());
+        contract.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "Mint limit of 2 per account reached")]
+    fn test_mint_limit_per_account() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_max_mints_per_account(Some(2));
+
+        let first = mint_egg(&mut context, &mut contract, accounts(1));
+        mint_egg(&mut context, &mut contract, accounts(1));
+        assert_eq!(contract.mints_for_account(accounts(1).to_string()), 2);
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.nft_transfer(accounts(2), first, None, None);
+        assert_eq!(contract.mints_for_account(accounts(1).to_string()), 2);
+        contract.assert_invariants();
+
+        mint_egg(&mut context, &mut contract, accounts(1));
+    }
+
+    fn setup_sale_window(context: &mut VMContextBuilder, contract: &mut Contract) {
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_sale_window(Some(1_000), Some(2_000));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+    }
+
+    fn at_ms(context: &mut VMContextBuilder, ms: u64) {
+        testing_env!(context.block_timestamp(ms * 1000000).build());
+    }
+
+    #[test]
+    fn test_sale_status_boundaries() {
+        let (mut context, mut contract) = setup_contract();
+        assert_eq!(contract.sale_status(), "live".to_string());
+        setup_sale_window(&mut context, &mut contract);
+
+        at_ms(&mut context, 999);
+        assert_eq!(contract.sale_status(), "not_started".to_string());
+        at_ms(&mut context, 1_000);
+        assert_eq!(contract.sale_status(), "live".to_string());
+        at_ms(&mut context, 1_999);
+        assert_eq!(contract.sale_status(), "live".to_string());
+        at_ms(&mut context, 2_000);
+        assert_eq!(contract.sale_status(), "ended".to_string());
+    }
+
+    #[test]
+    fn test_mint_during_sale_window() {
+        let (mut context, mut contract) = setup_contract();
+        setup_sale_window(&mut context, &mut contract);
+        at_ms(&mut context, 1_500);
+
+        mint_egg(&mut context, &mut contract, accounts(1));
+        assert_eq!(contract.nft_tokens_for_owner(accounts(1).to_string(), None, None).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The sale is not live")]
+    fn test_mint_before_sale_window() {
+        let (mut context, mut contract) = setup_contract();
+        setup_sale_window(&mut context, &mut contract);
+        at_ms(&mut context, 999);
+
+        mint_egg(&mut context, &mut contract, accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "The sale is not live")]
+    fn test_mint_after_sale_window() {
+        let (mut context, mut contract) = setup_contract();
+        setup_sale_window(&mut context, &mut contract);
+        at_ms(&mut context, 2_000);
+
+        mint_egg(&mut context, &mut contract, accounts(1));
+    }
+
+    #[test]
+    fn test_owner_mints_outside_sale_window() {
+        let (mut context, mut contract) = setup_contract();
+        setup_sale_window(&mut context, &mut contract);
+        at_ms(&mut context, 5_000);
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+
+        mint_egg(&mut context, &mut contract, accounts(0));
+        assert_eq!(contract.nft_tokens_for_owner(accounts(0).to_string(), None, None).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The sale is not live")]
+    fn test_owner_signed_call_through_other_contract_cannot_mint_outside_sale_window() {
+        let (mut context, mut contract) = setup_contract();
+        setup_sale_window(&mut context, &mut contract);
+        at_ms(&mut context, 5_000);
+
+        testing_env!(context
+            .signer_account_id(accounts(0))
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_mint_egg(accounts(3).to_string(), None);
+    }
+
+    #[test]
+    fn test_mint_refunds_one_yocto_overpayment() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT + 1)
+            .build());
+        contract.nft_mint_egg(accounts(1).to_string(), None);
+
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(receipts.contains(&format!("\"receiver_id\":\"{}\"", accounts(1))));
+        assert!(receipts.contains("\"Transfer\":{\"deposit\":1}"));
+    }
+
+    #[test]
+    fn test_mint_with_exact_deposit_creates_no_refund() {
+        let (mut context, mut contract) = setup_contract();
+        mint_egg(&mut context, &mut contract, accounts(1));
+
+        assert!(get_created_receipts().is_empty());
+    }
+
+    #[test]
+    fn test_mints_are_unlimited_by_default() {
+        let (mut context, mut contract) = setup_contract();
+        assert_eq!(contract.get_max_mints_per_account(), None);
+
+        for _ in 0..5 {
+            mint_egg(&mut context, &mut contract, accounts(1));
+        }
+
+        assert_eq!(contract.mints_for_account(accounts(1).to_string()), 5);
+        assert_eq!(contract.nft_tokens_for_owner(accounts(1).to_string(), None, None).len(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid receiver account id")]
+    fn test_mint_egg_rejects_invalid_receiver() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+
+        contract.nft_mint_egg("Not A Valid Account!".to_string(), None);
+    }
+
+    #[test]
+    fn test_airdrop_species_to_many_receivers() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT * 3)
+            .build());
+
+        let receivers = vec![accounts(1).to_string(), accounts(2).to_string(), accounts(3).to_string()];
+        contract.nft_airdrop(receivers.clone(), 7);
+
+        for receiver_id in receivers {
+            let tokens = contract.nft_tokens_for_owner(receiver_id, None, Some(10));
+            assert_eq!(tokens.len(), 1);
+            assert_eq!(contract.egg_per_token_id.get(&tokens[0].token_id).unwrap(), 7);
+        }
+        assert_eq!(contract.nft_tokens_by_species(7, None, None).len(), 3);
+
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].starts_with("EVENT_JSON:"));
+        assert!(logs[0].contains("\"event\":\"nft_mint\""));
+        assert!(logs[0].contains(&format!("\"owner_id\":\"{}\"", accounts(3))));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_mint_egg_of_chosen_species() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+
+        let token_id = contract.nft_mint_egg_species(accounts(1).to_string(), 12);
+
+        assert_eq!(contract.owner_of(token_id.clone()), Some(accounts(1).to_string()));
+        assert_eq!(contract.egg_per_token_id.get(&token_id), Some(12));
+        assert_eq!(contract.level_per_token(token_id), 0);
+        assert!(get_logs()[0].contains("\"event\":\"nft_mint\""));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_required_templates_allow_configured_species() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_templates_required(true);
+        let mut template = sample_token_metadata();
+        template.title = Some("Ember Egg".to_string());
+        contract.add_metadata("egg".to_string(), template);
+
+        testing_env!(context.attached_deposit(MINIMUM_EARLY_DEPOSIT).build());
+        let token_id = contract.nft_mint_egg_species(accounts(1).to_string(), 1);
+        let metadata = contract.nft_token(token_id).unwrap().metadata.unwrap();
+        assert_eq!(metadata.title, Some("Ember Egg".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Species 2 is not configured")]
+    fn test_required_templates_reject_unconfigured_species() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_templates_required(true);
+        contract.add_metadata("egg".to_string(), sample_token_metadata());
+
+        testing_env!(context.attached_deposit(MINIMUM_EARLY_DEPOSIT).build());
+        contract.nft_mint_egg_species(accounts(1).to_string(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Species 2 is not configured")]
+    fn test_required_templates_reject_reroll_into_unconfigured_species() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_templates_required(true);
+        contract.add_metadata("egg".to_string(), sample_token_metadata());
+
+        testing_env!(context.attached_deposit(MINIMUM_EARLY_DEPOSIT).build());
+        let token_id = contract.nft_mint_egg_species(accounts(1).to_string(), 1);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.internal_reroll_egg(&token_id, &FixedRandomSource(vec![1]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid species")]
+    fn test_mint_egg_of_species_rejects_out_of_range() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+
+        contract.nft_mint_egg_species(accounts(1).to_string(), SPECIES_COUNT + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn test_mint_egg_of_species_is_owner_only() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+
+        contract.nft_mint_egg_species(accounts(1).to_string(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid species")]
+    fn test_airdrop_rejects_invalid_species() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+
+        contract.nft_airdrop(vec![accounts(1).to_string()], SPECIES_COUNT + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Batches must hold between 1 and 50 items")]
+    fn test_airdrop_rejects_oversized_batch() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+
+        contract.nft_airdrop(vec![accounts(1).to_string(); MAX_BATCH + 1], 1);
+    }
+
+    #[test]
+    fn test_copies_counts_mints_per_template() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+
+        let copies_of = |contract: &Contract, token_id: TokenId| {
+            contract.nft_token(token_id).unwrap().metadata.unwrap().copies.unwrap()
+        };
+
+        let first = contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![2]));
+        let second = contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![2]));
+        let other = contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![3]));
+        let third = contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![2]));
+
+        assert_eq!(copies_of(&contract, first), 1);
+        assert_eq!(copies_of(&contract, second), 2);
+        assert_eq!(copies_of(&contract, third), 3);
+        assert_eq!(copies_of(&contract, other), 1);
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_token_ids_are_monotonic_past_digit_boundary() {
+        let (mut context, mut contract) = setup_contract();
+        contract.current_token_id = 98;
+
+        let ids: Vec<TokenId> = (0..3)
+            .map(|_| mint_egg(&mut context, &mut contract, accounts(1)))
+            .collect();
+        assert_eq!(ids, vec!["99".to_string(), "100".to_string(), "101".to_string()]);
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), ids[0].clone());
+        assert_eq!(evolved_id, "102".to_string());
+        assert_eq!(contract.current_token_id, 102);
+    }
+
+    const DROP_END_MS: u64 = 1_000;
+
+    fn enable_deterministic_mint(context: &mut VMContextBuilder, contract: &mut Contract, seed: &str) {
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_sale_window(None, Some(DROP_END_MS));
+        contract.set_commit_seed_hash(Base64VecU8(env::sha256(seed.as_bytes())));
+        contract.set_deterministic_mint(true);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+    }
+
+    fn reveal_drop(context: &mut VMContextBuilder, contract: &mut Contract, seed: &str) {
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .block_timestamp(DROP_END_MS * 1000000)
+            .build());
+        contract.reveal_commit_seed(seed.to_string());
+        assert_eq!(contract.resolve_revealed_eggs(None), 0);
+    }
+
+    #[test]
+    fn test_deterministic_mint_is_reproducible() {
+        let species_for = |seed: &str| {
+            let (mut context, mut contract) = setup_contract();
+            enable_deterministic_mint(&mut context, &mut contract, seed);
+            let token_ids: Vec<TokenId> = (0..8).map(|_| mint_egg(&mut context, &mut contract, accounts(1))).collect();
+            assert!(token_ids.iter().all(|token_id| !contract.is_species_revealed(token_id.clone())));
+
+            reveal_drop(&mut context, &mut contract, seed);
+            contract.assert_invariants();
+            token_ids
+                .iter()
+                .map(|token_id| contract.egg_per_token_id.get(token_id).unwrap())
+                .collect::<Vec<u64>>()
+        };
+
+        let first = species_for("nearmon-drop-1");
+        assert_eq!(first, species_for("nearmon-drop-1"));
+
+        let (_, contract) = setup_contract();
+        for (index, species) in first.iter().enumerate() {
+            let source = CommitSeedRandomSource {
+                seed: "nearmon-drop-1".to_string(),
+                token_id: (index + 1).to_string(),
+            };
+            assert_eq!(contract.get_random_number(&source, SPECIES_COUNT) + 1, *species);
+        }
+    }
+
+    #[test]
+    fn test_commit_seed_revealed_after_sale() {
+        let (mut context, mut contract) = setup_contract();
+        enable_deterministic_mint(&mut context, &mut contract, "nearmon-drop-1");
+
+        assert_eq!(contract.commit_seed_hash().unwrap().0, env::sha256(b"nearmon-drop-1"));
+        assert!(contract.revealed_commit_seed().is_none());
+
+        reveal_drop(&mut context, &mut contract, "nearmon-drop-1");
+        assert_eq!(contract.revealed_commit_seed(), Some("nearmon-drop-1".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Commit seed can only be revealed after the sale ends")]
+    fn test_commit_seed_reveal_waits_for_sale_end() {
+        let (mut context, mut contract) = setup_contract();
+        enable_deterministic_mint(&mut context, &mut contract, "nearmon-drop-1");
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.reveal_commit_seed("nearmon-drop-1".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Seed does not match the commitment")]
+    fn test_commit_seed_reveal_checks_commitment() {
+        let (mut context, mut contract) = setup_contract();
+        enable_deterministic_mint(&mut context, &mut contract, "nearmon-drop-1");
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .block_timestamp(DROP_END_MS * 1000000)
+            .build());
+        contract.reveal_commit_seed("nearmon-drop-2".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Deterministic mode needs a sale end time")]
+    fn test_deterministic_mint_requires_sale_end() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_commit_seed_hash(Base64VecU8(env::sha256(b"nearmon-drop-1")));
+        contract.set_deterministic_mint(true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Egg species is not revealed yet")]
+    fn test_unrevealed_egg_cannot_evolve() {
+        let (mut context, mut contract) = setup_contract();
+        enable_deterministic_mint(&mut context, &mut contract, "nearmon-drop-1");
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        assert!(contract.evolve_blockers(token_id.clone()).contains(&"Egg species is not revealed yet".to_string()));
+
+        evolve(&mut context, &mut contract, accounts(1), token_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Commit seed cannot change in deterministic mode")]
+    fn test_commit_seed_frozen_in_deterministic_mode() {
+        let (mut context, mut contract) = setup_contract();
+        enable_deterministic_mint(&mut context, &mut contract, "nearmon-drop-1");
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_commit_seed_hash(Base64VecU8(env::sha256(b"other")));
+    }
+
+    fn setup_capped_contract(max_supply: u64) -> (VMContextBuilder, Contract) {
+        let (context, contract) = setup_contract();
+        let metadata = contract.nft_metadata();
+        let contract = Contract::new(accounts(0), metadata, Some(max_supply));
+        (context, contract)
+    }
+
+    #[test]
+    fn test_max_supply_allows_minting_up_to_cap() {
+        let (mut context, mut contract) = setup_capped_contract(2);
+        let egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+        mint_egg(&mut context, &mut contract, accounts(1));
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), egg_id);
+        assert_eq!(contract.level_per_token(evolved_id), 1);
+        assert_eq!(contract.total_minted(), U64(2));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "Max supply of 2 reached")]
+    fn test_max_supply_rejects_mint_past_cap() {
+        let (mut context, mut contract) = setup_capped_contract(2);
+        mint_egg(&mut context, &mut contract, accounts(1));
+        mint_egg(&mut context, &mut contract, accounts(1));
+        mint_egg(&mut context, &mut contract, accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Max supply of 1 reached")]
+    fn test_max_supply_applies_to_airdrops() {
+        let (mut context, mut contract) = setup_capped_contract(1);
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(2 * MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_airdrop(vec![accounts(1).to_string(), accounts(2).to_string()], 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max supply can only be lowered")]
+    fn test_max_supply_cannot_be_raised() {
+        let (mut context, mut contract) = setup_capped_contract(2);
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.lower_max_supply(1);
+        contract.lower_max_supply(5);
+    }
+
+    #[test]
+    fn test_weighted_species_distribution() {
+        let (mut context, mut contract) = setup_contract();
+        let mut weights = vec![0u64; SPECIES_COUNT as usize];
+        weights[0] = 8;
+        weights[1] = 4;
+        weights[2] = 4;
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_species_weights(weights);
+
+        for seed in 0..=255u8 {
+            contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![seed]));
+        }
+
+        assert_eq!(contract.supply_for_species(1), U128(128));
+        assert_eq!(contract.supply_for_species(2), U128(64));
+        assert_eq!(contract.supply_for_species(3), U128(64));
+        assert_eq!(contract.supply_for_species(4), U128(0));
+    }
+
+    #[test]
+    fn test_weights_above_one_byte_use_wider_roll() {
+        let (mut context, mut contract) = setup_contract();
+        let mut weights = vec![0u64; SPECIES_COUNT as usize];
+        weights[0] = 300;
+        weights[1] = 300;
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_species_weights(weights);
+
+        let token_id = contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![1, 44]));
+        assert_eq!(contract.egg_per_token_id.get(&token_id), Some(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected one weight per species")]
+    fn test_species_weights_length_checked() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_species_weights(vec![1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "E084: Species weights must sum to at most u64::MAX")]
+    fn test_species_weights_total_overflow_rejected() {
+        let (mut context, mut contract) = setup_contract();
+        let mut weights = vec![1u64; SPECIES_COUNT as usize];
+        weights[0] = u64::MAX;
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_species_weights(weights);
+    }
+
+    #[test]
+    fn test_species_weights_summing_to_max_accepted() {
+        let (mut context, mut contract) = setup_contract();
+        let mut weights = vec![0u64; SPECIES_COUNT as usize];
+        weights[0] = u64::MAX - 1;
+        weights[1] = 1;
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_species_weights(weights.clone());
+        assert_eq!(contract.get_species_weights(), weights);
+        assert!(contract.preview_species() <= 2);
+    }
+
+    #[test]
+    fn test_preview_species_is_in_range() {
+        let (mut context, mut contract) = setup_contract();
+        for seed in 0..8u8 {
+            testing_env!(context.random_seed(vec![seed; 32]).build());
+            let species = contract.preview_species();
+            assert!(species >= 1 && species <= SPECIES_COUNT);
+            assert_eq!(contract.preview_species(), species);
+        }
+
+        let mut weights = vec![0u64; SPECIES_COUNT as usize];
+        weights[6] = 1;
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_species_weights(weights);
+        assert_eq!(contract.preview_species(), 7);
+    }
+
+    #[test]
+    fn test_preview_metadata_for_each_stage() {
+        let (_, contract) = setup_contract();
+        for level in 0..4 {
+            let preview = contract.preview_metadata(level, 3);
+            assert_eq!(preview.title, get_built_in_metadata(level, 3).unwrap().title);
+            assert_eq!(preview.copies, Some(1));
+            assert!(TokenExtra::parse(preview.extra.as_ref().unwrap()).evolve_at >= EVOLVE_COOLDOWN_MS);
+        }
+        assert!(contract.copies_per_template.get(&(0, 3)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "No template for level 4")]
+    fn test_preview_metadata_rejects_unknown_level() {
+        let (_, contract) = setup_contract();
+        contract.preview_metadata(4, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid species")]
+    fn test_preview_metadata_rejects_unknown_species() {
+        let (_, contract) = setup_contract();
+        contract.preview_metadata(0, SPECIES_COUNT + 1);
+    }
+
+    #[test]
+    fn test_supply_per_species_counters() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+
+        let mut species_one = vec![];
+        for seed in vec![0u8, 0, 1, 0] {
+            let token_id = contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![seed]));
+            if seed == 0 {
+                species_one.push(token_id);
+            }
+        }
+        assert_eq!(contract.supply_for_species(1), U128(3));
+        assert_eq!(contract.supply_for_species(2), U128(1));
+        assert_eq!(contract.supply_for_species(3), U128(0));
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.nft_burn(species_one[0].clone());
+        contract.nft_burn(species_one[1].clone());
+        assert_eq!(contract.supply_for_species(1), U128(1));
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), species_one[2].clone());
+        assert_eq!(contract.egg_per_token_id.get(&evolved_id).unwrap(), 1);
+        assert_eq!(contract.supply_for_species(1), U128(1));
+
+        let mut all = contract.supply_per_species_all();
+        all.sort_by_key(|(species, _)| *species);
+        assert_eq!(all, vec![(1, U128(1)), (2, U128(1))]);
+    }
+
+    #[test]
+    fn test_nft_token_without_metadata_degrades_gracefully() {
+        let (_, mut contract) = setup_contract();
+        contract.tokens.owner_by_id.insert(&"7".to_string(), &accounts(1).to_string());
+
+        let token = contract.nft_token("7".to_string()).unwrap();
+        assert_eq!(token.owner_id, accounts(1).to_string());
+        assert!(token.metadata.is_none());
+    }
+
+    #[test]
+    fn test_total_owners_tracks_distinct_holders() {
+        let (mut context, mut contract) = setup_contract();
+        assert_eq!(contract.total_owners(), 0);
+
+        let first = mint_egg(&mut context, &mut contract, accounts(1));
+        assert_eq!(contract.total_owners(), 1);
+
+        let second = mint_egg(&mut context, &mut contract, accounts(1));
+        assert_eq!(contract.total_owners(), 1);
+
+        mint_egg(&mut context, &mut contract, accounts(2));
+        assert_eq!(contract.total_owners(), 2);
+
+        let evolved = evolve(&mut context, &mut contract, accounts(1), first);
+        assert_eq!(contract.total_owners(), 2);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(accounts(3), second, None, None);
+        assert_eq!(contract.total_owners(), 3);
+
+        contract.nft_burn(evolved.clone());
+        assert_eq!(contract.total_owners(), 2);
+    }
+
+    fn assert_owner_counts_match(contract: &Contract) {
+        for index in 1..5 {
+            let account_id = accounts(index).to_string();
+            let held = contract.token_ids_for_owner(account_id.clone(), None, None).len() as u128;
+            assert_eq!(contract.nft_supply_for_owner(account_id), U128(held));
+        }
+        let holders = (1..5)
+            .filter(|index| contract.nft_supply_for_owner(accounts(*index).to_string()).0 > 0)
+            .count() as u64;
+        assert_eq!(contract.total_owners(), holders);
+    }
+
+    #[test]
+    fn test_count_per_owner_follows_every_operation() {
+        let (mut context, mut contract) = setup_contract();
+        assert_owner_counts_match(&contract);
+
+        let first = mint_egg(&mut context, &mut contract, accounts(1));
+        let second = mint_egg(&mut context, &mut contract, accounts(1));
+        mint_egg(&mut context, &mut contract, accounts(2));
+        assert_owner_counts_match(&contract);
+
+        let evolved = evolve(&mut context, &mut contract, accounts(1), first);
+        assert_owner_counts_match(&contract);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        contract.nft_transfer(accounts(3), second.clone(), None, None);
+        assert_owner_counts_match(&contract);
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.nft_transfer_call(accounts(4), second.clone(), None, None, "return".to_string());
+        assert_owner_counts_match(&contract);
+
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Successful(b"true".to_vec()),
+        );
+        assert!(!contract.nft_resolve_transfer(accounts(3).to_string(), accounts(4).to_string(), second, None));
+        assert_eq!(contract.nft_supply_for_owner(accounts(3).to_string()), U128(1));
+        assert_owner_counts_match(&contract);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        contract.nft_burn(evolved);
+        assert_owner_counts_match(&contract);
+        assert_eq!(contract.total_owners(), 2);
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_nft_tokens_for_owner_sorted_by_level() {
+        let (mut context, mut contract) = setup_contract();
+        let ids: Vec<TokenId> = (0..10).map(|_| mint_egg(&mut context, &mut contract, accounts(1))).collect();
+        let first = evolve(&mut context, &mut contract, accounts(1), ids[0].clone());
+        let second = evolve(&mut context, &mut contract, accounts(1), first);
+        let third = evolve(&mut context, &mut contract, accounts(1), ids[9].clone());
+
+        let sorted_ids = |ascending: bool, from_index: Option<U128>, limit: Option<u64>| -> Vec<TokenId> {
+            contract
+                .nft_tokens_for_owner_sorted(accounts(1).to_string(), ascending, from_index, limit)
+                .into_iter()
+                .map(|token| token.token_id)
+                .collect()
+        };
+
+        let mut expected: Vec<TokenId> = ids[1..9].to_vec();
+        expected.push(third.clone());
+        expected.push(second.clone());
+        assert_eq!(sorted_ids(true, None, None), expected);
+
+        expected.reverse();
+        assert_eq!(sorted_ids(false, None, None), expected);
+        assert_eq!(sorted_ids(false, Some(U128(1)), Some(2)), vec![third, ids[8].clone()]);
+        assert!(sorted_ids(true, None, None).iter().all(|id| contract.owner_of(id.clone()).is_some()));
+    }
+
+    #[test]
+    fn test_nft_tokens_for_owner_filtered() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        let species_one: Vec<TokenId> = (0..3)
+            .map(|_| contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![0])))
+            .collect();
+        let species_two = contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![1]));
+        let evolved_one = evolve(&mut context, &mut contract, accounts(1), species_one[0].clone());
+        let evolved_two = evolve(&mut context, &mut contract, accounts(1), species_two);
+
+        let ids = |tokens: Vec<Token>| tokens.into_iter().map(|token| token.token_id).collect::<Vec<_>>();
+        let owner = accounts(1).to_string();
+
+        let both = ids(contract.nft_tokens_for_owner_filtered(owner.clone(), Some(1), Some(1), None, None));
+        assert_eq!(both, vec![evolved_one.clone()]);
+
+        let level_only = ids(contract.nft_tokens_for_owner_filtered(owner.clone(), None, Some(1), None, None));
+        assert_eq!(level_only.len(), 2);
+        assert!(level_only.contains(&evolved_two));
+
+        let species_only = ids(contract.nft_tokens_for_owner_filtered(owner.clone(), Some(1), None, None, None));
+        assert!(species_only.contains(&species_one[1]));
+        assert!(species_only.contains(&species_one[2]));
+        assert!(species_only.contains(&evolved_one));
+        assert!(!species_only.contains(&evolved_two));
+
+        let page = ids(contract.nft_tokens_for_owner_filtered(owner.clone(), Some(1), None, Some(U128(1)), Some(1)));
+        assert_eq!(page, vec![species_only[1].clone()]);
+
+        assert!(contract.nft_tokens_for_owner_filtered(owner, Some(3), None, None, None).is_empty());
+    }
+
+    #[test]
+    fn test_token_ids_for_owner_matches_tokens() {
+        let (mut context, mut contract) = setup_contract();
+        for _ in 0..4 {
+            mint_egg(&mut context, &mut contract, accounts(1));
+        }
+
+        let full_ids: Vec<TokenId> = contract
+            .nft_tokens_for_owner(accounts(1).to_string(), Some(U128(1)), Some(2))
+            .into_iter()
+            .map(|token| token.token_id)
+            .collect();
+        assert_eq!(full_ids.len(), 2);
+        assert_eq!(contract.token_ids_for_owner(accounts(1).to_string(), Some(U128(1)), Some(2)), full_ids);
+        assert_eq!(contract.token_ids_for_owner(accounts(1).to_string(), None, None).len(), 4);
+        assert!(contract.token_ids_for_owner(accounts(2).to_string(), None, None).is_empty());
+    }
+
+    #[test]
+    fn test_owner_history_records_handovers_in_order() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).block_timestamp(1_000 * 1000000).build());
+        contract.set_owner_id(accounts(1));
+        assert_eq!(get_logs(), vec![format!("Owner changed from {} to {} at 1000", accounts(0), accounts(1))]);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).block_timestamp(2_000 * 1000000).build());
+        contract.set_owner_id(accounts(2));
+
+        assert_eq!(contract.owner_id, accounts(2).to_string());
+        assert_eq!(
+            contract.owner_history(),
+            vec![
+                (accounts(0).to_string(), U64(1_000)),
+                (accounts(1).to_string(), U64(2_000)),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn test_previous_owner_cannot_set_owner() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_owner_id(accounts(1));
+
+        contract.set_owner_id(accounts(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "E001: Unauthorized")]
+    fn test_owner_signed_cross_contract_call_cannot_set_owner() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .signer_account_id(accounts(0))
+            .predecessor_account_id(accounts(3))
+            .build());
+
+        contract.set_owner_id(accounts(3));
+    }
+
+    #[test]
+    fn test_nft_token_approvals() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        assert!(contract.nft_token(token_id.clone()).unwrap().approved_account_ids.is_none());
+
+        let mut approvals = HashMap::new();
+        approvals.insert(accounts(2).to_string(), 1u64);
+        contract.tokens.approvals_by_id.as_mut().unwrap().insert(&token_id, &approvals);
+
+        assert_eq!(contract.nft_token(token_id.clone()).unwrap().approved_account_ids, Some(approvals));
+        assert!(contract.nft_token_view(token_id.clone()).unwrap().approved_account_ids.is_none());
+        assert_eq!(
+            contract.nft_token_view(token_id.clone()).unwrap().metadata,
+            contract.nft_token(token_id).unwrap().metadata
+        );
+    }
+
+    #[test]
+    fn test_nft_approve_many() {
+        let (mut context, mut contract) = setup_contract();
+        let token_ids: Vec<TokenId> = (0..3)
+            .map(|_| mint_egg(&mut context, &mut contract, accounts(1)))
+            .collect();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        let approval_ids = contract.nft_approve_many(token_ids.clone(), accounts(2), None);
+        assert_eq!(approval_ids, vec![U64(1), U64(1), U64(1)]);
+
+        for token_id in token_ids.iter() {
+            let approvals = contract.nft_token(token_id.clone()).unwrap().approved_account_ids.unwrap();
+            assert_eq!(approvals.get(&accounts(2).to_string()), Some(&1));
+        }
+
+        let approval_ids = contract.nft_approve_many(vec![token_ids[0].clone()], accounts(3), None);
+        assert_eq!(approval_ids, vec![U64(2)]);
+    }
+
+    #[test]
+    fn test_nft_approve_many_notifies_with_msg() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_approve_many(vec![token_id], accounts(2), Some("list".to_string()));
+
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(receipts.contains("nft_on_approve"));
+    }
+
+    #[test]
+    #[should_panic(expected = "You are not the Token owner")]
+    fn test_nft_approve_many_rejects_unowned_token() {
+        let (mut context, mut contract) = setup_contract();
+        let owned = mint_egg(&mut context, &mut contract, accounts(1));
+        let not_owned = mint_egg(&mut context, &mut contract, accounts(2));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_approve_many(vec![owned, not_owned], accounts(3), None);
+    }
+
+    // Approvals use `MAX_APPROVE_BATCH` instead of `MAX_BATCH`, since every
+    // approval may schedule an `nft_on_approve` call.
+    #[test]
+    #[should_panic(expected = "Batches must hold between 1 and 10 items")]
+    fn test_nft_approve_many_rejects_oversized_batch() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        let token_ids = (0..MAX_APPROVE_BATCH + 1).map(|id| id.to_string()).collect();
+        contract.nft_approve_many(token_ids, accounts(3), None);
+    }
+
+    #[test]
+    fn test_grant_xp_batch_skips_missing_tokens() {
+        let (mut context, mut contract) = setup_contract();
+        let first = mint_egg(&mut context, &mut contract, accounts(1));
+        let second = mint_egg(&mut context, &mut contract, accounts(2));
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.grant_xp(first.clone(), 5);
+        let skipped = contract.grant_xp_batch(vec![
+            (first.clone(), 10),
+            ("404".to_string(), 7),
+            (second.clone(), 3),
+        ]);
+
+        assert_eq!(skipped, vec!["404".to_string()]);
+        assert_eq!(contract.xp_for_token(first), U64(15));
+        assert_eq!(contract.xp_for_token(second), U64(3));
+        assert_eq!(contract.xp_for_token("404".to_string()), U64(0));
+    }
+
+    #[test]
+    fn test_xp_follows_evolution() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.grant_xp(token_id.clone(), 42);
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), token_id.clone());
+        assert_eq!(contract.xp_for_token(evolved_id), U64(42));
+        assert_eq!(contract.xp_for_token(token_id), U64(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Batches must hold between 1 and 50 items")]
+    fn test_grant_xp_batch_is_bounded() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let grants = (0..MAX_BATCH + 1).map(|id| (id.to_string(), 1)).collect();
+        contract.grant_xp_batch(grants);
+    }
+
+    #[test]
+    fn test_approved_accounts_after_approve_and_revoke() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        assert!(contract.approved_accounts(token_id.clone()).is_empty());
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_approve(token_id.clone(), accounts(2), None);
+        contract.nft_approve(token_id.clone(), accounts(3), None);
+        assert_eq!(
+            contract.approved_accounts(token_id.clone()),
+            vec![(accounts(2).to_string(), U64(1)), (accounts(3).to_string(), U64(2))]
+        );
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.nft_revoke(token_id.clone(), accounts(2));
+        assert_eq!(contract.approved_accounts(token_id.clone()), vec![(accounts(3).to_string(), U64(2))]);
+
+        contract.nft_revoke(token_id.clone(), accounts(3));
+        assert!(contract.approved_accounts(token_id.clone()).is_empty());
+        assert!(contract.nft_token(token_id).unwrap().approved_account_ids.is_none());
+    }
+
+    #[test]
+    fn test_revoke_one_keeps_other_approvals_and_counter() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_approve(token_id.clone(), accounts(2), None);
+        contract.nft_approve(token_id.clone(), accounts(3), None);
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.nft_revoke(token_id.clone(), accounts(2));
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        let event: Value = serde_json::from_str(logs[0].strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+        assert_eq!(event["standard"], "nearmon");
+        assert_eq!(event["event"], "approval_revoked");
+        assert_eq!(event["data"][0]["token_id"], token_id);
+        assert_eq!(event["data"][0]["account_ids"], serde_json::json!([accounts(2).to_string()]));
+        assert_eq!(contract.approved_accounts(token_id.clone()), vec![(accounts(3).to_string(), U64(2))]);
+
+        testing_env!(context.attached_deposit(MINIMUM_EARLY_DEPOSIT).build());
+        contract.nft_approve(token_id.clone(), accounts(2), None);
+        assert_eq!(contract.approved_accounts(token_id)[1], (accounts(2).to_string(), U64(3)));
+    }
+
+    #[test]
+    fn test_revoke_all_clears_approvals_and_resets_counter() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_approve(token_id.clone(), accounts(2), None);
+        contract.nft_approve(token_id.clone(), accounts(3), None);
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.nft_revoke_all(token_id.clone());
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        let event: Value = serde_json::from_str(logs[0].strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+        assert_eq!(event["event"], "approval_revoked");
+        assert_eq!(
+            event["data"][0]["account_ids"],
+            serde_json::json!([accounts(2).to_string(), accounts(3).to_string()])
+        );
+        assert!(contract.approved_accounts(token_id.clone()).is_empty());
+
+        testing_env!(context.attached_deposit(MINIMUM_EARLY_DEPOSIT).build());
+        contract.nft_approve(token_id.clone(), accounts(3), None);
+        assert_eq!(contract.approved_accounts(token_id), vec![(accounts(3).to_string(), U64(1))]);
+    }
+
+    #[test]
+    fn test_approved_transfer_with_current_approval_id() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_approve(token_id.clone(), accounts(3), None);
+
+        testing_env!(context.predecessor_account_id(accounts(3)).attached_deposit(1).build());
+        contract.nft_transfer(accounts(2), token_id.clone(), Some(1), None);
+        assert_eq!(contract.owner_of(token_id), Some(accounts(2).to_string()));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "approval_id does not match the current approval")]
+    fn test_stale_approval_id_is_rejected() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_approve(token_id.clone(), accounts(3), None);
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.nft_transfer(accounts(2), token_id.clone(), None, None);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.nft_transfer(accounts(1), token_id.clone(), None, None);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_approve(token_id.clone(), accounts(3), None);
+        assert_eq!(contract.approved_accounts(token_id.clone()), vec![(accounts(3).to_string(), U64(2))]);
+
+        testing_env!(context.predecessor_account_id(accounts(3)).attached_deposit(1).build());
+        contract.nft_transfer(accounts(4), token_id, Some(1), None);
+    }
+
+    fn approve_until(context: &mut VMContextBuilder, contract: &mut Contract, expires_at: u64) -> TokenId {
+        let token_id = mint_egg(context, contract, accounts(1));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_approve_until(token_id.clone(), accounts(3), U64(expires_at), None);
+        token_id
+    }
+
+    #[test]
+    fn test_unexpired_approval_can_transfer() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = approve_until(&mut context, &mut contract, 1_000);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(1)
+            .block_timestamp(999 * 1000000)
+            .build());
+        assert!(contract.nft_is_approved(token_id.clone(), accounts(3), Some(1)));
+        contract.nft_transfer(accounts(2), token_id.clone(), Some(1), None);
+        assert_eq!(contract.owner_of(token_id), Some(accounts(2).to_string()));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "Approval has expired")]
+    fn test_expired_approval_cannot_transfer() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = approve_until(&mut context, &mut contract, 1_000);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(1)
+            .block_timestamp(1_000 * 1000000)
+            .build());
+        assert!(!contract.nft_is_approved(token_id.clone(), accounts(3), None));
+        contract.nft_transfer(accounts(2), token_id, Some(1), None);
+    }
+
+    #[test]
+    fn test_reapproving_without_expiry_clears_it() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = approve_until(&mut context, &mut contract, 1_000);
+
+        contract.nft_approve(token_id.clone(), accounts(3), None);
+        assert!(contract.approval_expiry_by_id.get(&token_id).is_none());
+
+        testing_env!(context.block_timestamp(5_000 * 1000000).build());
+        assert!(contract.nft_is_approved(token_id, accounts(3), Some(2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Expiry must be in the future")]
+    fn test_approve_until_rejects_past_expiry() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.block_timestamp(2_000 * 1000000).build());
+        approve_until(&mut context, &mut contract, 1_000);
+    }
+
+    #[test]
+    fn test_nft_is_approved() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        assert!(!contract.nft_is_approved(token_id.clone(), accounts(2), None));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_approve(token_id.clone(), accounts(2), None);
+
+        assert!(contract.nft_is_approved(token_id.clone(), accounts(2), None));
+        assert!(contract.nft_is_approved(token_id.clone(), accounts(2), Some(1)));
+        assert!(!contract.nft_is_approved(token_id.clone(), accounts(2), Some(2)));
+        assert!(!contract.nft_is_approved(token_id.clone(), accounts(3), None));
+        assert!(!contract.nft_is_approved("404".to_string(), accounts(2), None));
+    }
+
+    #[test]
+    fn test_contract_source_metadata() {
+        let (_, contract) = setup_contract();
+        let source_metadata = serde_json::to_value(contract.contract_source_metadata()).unwrap();
+
+        assert_eq!(source_metadata["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(source_metadata["link"], "https://github.com/EdoWahdana/nearmon");
+        let standards: Vec<&str> = source_metadata["standards"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|standard| standard["standard"].as_str().unwrap())
+            .collect();
+        assert_eq!(standards, vec!["nep171", "nep177", "nep178", "nep181", "nep297", "nep330"]);
+    }
+
+    #[test]
+    fn test_token_metadata() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        assert_eq!(
+            contract.token_metadata(token_id.clone()),
+            contract.nft_token(token_id).unwrap().metadata
+        );
+        assert!(contract.token_metadata("404".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_admin_withdraw_surplus() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(1)
+            .account_balance(10 * MINIMUM_EARLY_DEPOSIT)
+            .storage_usage(1_000)
+            .build());
+        contract.admin_withdraw(U128(MINIMUM_EARLY_DEPOSIT), accounts(3));
+
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(receipts.contains(&format!("\"receiver_id\":\"{}\"", accounts(3))));
+        assert!(receipts.contains(&format!("\"deposit\":{}", MINIMUM_EARLY_DEPOSIT)));
+        assert_eq!(get_logs(), vec![format!("Withdrew {} yoctoNEAR to {}", MINIMUM_EARLY_DEPOSIT, accounts(3))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot withdraw more than the free balance")]
+    fn test_admin_withdraw_keeps_storage_reserve() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(1)
+            .account_balance(10 * MINIMUM_EARLY_DEPOSIT)
+            .storage_usage(1_000)
+            .build());
+
+        contract.admin_withdraw(U128(10 * MINIMUM_EARLY_DEPOSIT), accounts(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot withdraw more than the free balance")]
+    fn test_admin_withdraw_cannot_touch_storage_credits() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(5 * MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.deposit_storage();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(1)
+            .account_balance(10 * MINIMUM_EARLY_DEPOSIT)
+            .storage_usage(1_000)
+            .build());
+        contract.admin_withdraw(U128(4 * MINIMUM_EARLY_DEPOSIT), accounts(3));
+        contract.admin_withdraw(U128(MINIMUM_EARLY_DEPOSIT), accounts(3));
+    }
+
+    #[test]
+    fn test_total_storage_credits_follow_deposit_charge_and_withdraw() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(3 * MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.deposit_storage();
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.deposit_storage();
+        assert_eq!(contract.total_storage_credits, 6 * MINIMUM_EARLY_DEPOSIT);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(0).build());
+        contract.nft_mint_egg(accounts(1).to_string(), None);
+        assert_eq!(contract.storage_credit_of(accounts(1).to_string()), U128(2 * MINIMUM_EARLY_DEPOSIT));
+        assert_eq!(contract.total_storage_credits, 5 * MINIMUM_EARLY_DEPOSIT);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).attached_deposit(1).build());
+        contract.withdraw_storage_credit(U128(3 * MINIMUM_EARLY_DEPOSIT));
+        assert_eq!(contract.total_storage_credits, 2 * MINIMUM_EARLY_DEPOSIT);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn test_admin_withdraw_owner_only() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .account_balance(10 * MINIMUM_EARLY_DEPOSIT)
+            .build());
+
+        contract.admin_withdraw(U128(1), accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "E001: Unauthorized")]
+    fn test_admin_withdraw_rejects_owner_signed_cross_contract_call() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .signer_account_id(accounts(0))
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(1)
+            .account_balance(10 * MINIMUM_EARLY_DEPOSIT)
+            .build());
+
+        contract.admin_withdraw(U128(1), accounts(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "E001: Unauthorized")]
+    fn test_unauthorized_error_code() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.set_evolve_fee(U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "E003: Token not found")]
+    fn test_token_not_found_error_code() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.attached_deposit(1).build());
+        contract.nft_burn("404".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "E004: You are not the Token owner")]
+    fn test_not_token_owner_error_code() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        testing_env!(context.predecessor_account_id(accounts(2)).attached_deposit(1).build());
+        contract.nft_burn(token_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "E011: Must attach")]
+    fn test_insufficient_deposit_error_code() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(0).build());
+        contract.nft_mint_egg(accounts(1).to_string(), None);
+    }
+
+    #[test]
+    fn test_owner_of() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        assert_eq!(contract.owner_of(token_id.clone()), Some(contract.nft_token(token_id).unwrap().owner_id));
+        assert_eq!(contract.owner_of("404".to_string()), None);
+    }
+
+    #[test]
+    fn test_nft_tokens_batch_mixes_found_and_missing() {
+        let (mut context, mut contract) = setup_contract();
+        let first = mint_egg(&mut context, &mut contract, accounts(1));
+        let second = mint_egg(&mut context, &mut contract, accounts(2));
+
+        let tokens = contract.nft_tokens_batch(vec![
+            first.clone(),
+            "404".to_string(),
+            second.clone(),
+        ]);
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0].as_ref().unwrap().token_id, first);
+        assert!(tokens[1].is_none());
+        assert_eq!(tokens[2].as_ref().unwrap().owner_id, accounts(2).to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Batches must hold between 1 and 50 items")]
+    fn test_nft_tokens_batch_caps_input() {
+        let (_, contract) = setup_contract();
+        let token_ids = (0..MAX_BATCH + 1).map(|id| id.to_string()).collect();
+
+        contract.nft_tokens_batch(token_ids);
+    }
+
+    #[test]
+    fn test_nft_tokens_batch_of_nothing_is_empty() {
+        let (_, contract) = setup_contract();
+        assert!(contract.nft_tokens_batch(vec![]).is_empty());
+    }
+
+    #[test]
+    fn test_enumeration_from_index_past_end_is_empty() {
+        let (mut context, mut contract) = setup_contract();
+        mint_egg(&mut context, &mut contract, accounts(1));
+        mint_egg(&mut context, &mut contract, accounts(1));
+
+        let owner_id = accounts(1).to_string();
+        assert_eq!(contract.nft_tokens_for_owner(owner_id.clone(), Some(U128(1)), None).len(), 1);
+        assert!(contract.nft_tokens_for_owner(owner_id.clone(), Some(U128(2)), None).is_empty());
+        assert!(contract.nft_tokens_for_owner(owner_id, Some(U128(u128::MAX)), Some(10)).is_empty());
+        assert!(contract.nft_tokens_by_level(0, Some(U128(5)), None).is_empty());
+    }
+
+    #[test]
+    fn test_enumeration_limit_is_capped() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT * 150)
+            .build());
+        for _ in 0..3 {
+            let receivers = vec![accounts(1).to_string(); MAX_BATCH];
+            contract.nft_airdrop(receivers, 1);
+        }
+
+        let owner_id = accounts(1).to_string();
+        assert_eq!(contract.nft_tokens_for_owner(owner_id.clone(), None, Some(u64::MAX)).len(), MAX_PAGE_LIMIT as usize);
+        assert_eq!(contract.nft_tokens_for_owner(owner_id, None, None).len(), MAX_PAGE_LIMIT as usize);
+        assert_eq!(contract.nft_tokens_by_species(1, None, Some(1000)).len(), MAX_PAGE_LIMIT as usize);
+        assert_eq!(contract.nft_tokens_by_species(1, Some(U128(120)), Some(1000)).len(), 30);
+    }
+
+    #[test]
+    fn test_nft_tokens_by_species() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+
+        for seed in vec![0u8, 1, 0, 0] {
+            contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![seed]));
+        }
+
+        let species_one: Vec<TokenId> = contract
+            .nft_tokens_by_species(1, None, None)
+            .into_iter()
+            .map(|token| token.token_id)
+            .collect();
+        assert_eq!(species_one, vec!["1".to_string(), "3".to_string(), "4".to_string()]);
+
+        let page = contract.nft_tokens_by_species(1, Some(U128(1)), Some(1));
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].token_id, "3".to_string());
+
+        assert_eq!(contract.nft_tokens_by_species(2, None, None).len(), 1);
+        assert!(contract.nft_tokens_by_species(3, None, None).is_empty());
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), "3".to_string());
+        let species_one: Vec<TokenId> = contract
+            .nft_tokens_by_species(1, None, None)
+            .into_iter()
+            .map(|token| token.token_id)
+            .collect();
+        assert_eq!(species_one.len(), 3);
+        assert!(species_one.contains(&evolved_id));
+        assert!(!species_one.contains(&"3".to_string()));
+    }
+
+    #[test]
+    fn test_nickname_follows_transfer_and_evolve() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.set_nickname(token_id.clone(), "  Sparky ".to_string());
+        assert_eq!(contract.nickname(token_id.clone()), Some("Sparky".to_string()));
+
+        let detailed = contract.tokens_detailed_for_owner(accounts(1).to_string(), None, None);
+        assert_eq!(detailed.len(), 1);
+        assert_eq!(detailed[0].token.token_id, token_id);
+        assert_eq!(detailed[0].nickname, Some("Sparky".to_string()));
+        assert_eq!(detailed[0].game.as_ref().unwrap().level, 0);
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.nft_transfer(accounts(2), token_id.clone(), None, None);
+        assert_eq!(contract.nickname(token_id.clone()), Some("Sparky".to_string()));
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(2), token_id.clone());
+        assert_eq!(contract.nickname(evolved_id.clone()), Some("Sparky".to_string()));
+        assert!(contract.nickname(token_id).is_none());
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.set_nickname(evolved_id.clone(), String::new());
+        assert!(contract.nickname(evolved_id).is_none());
+        contract.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "Nickname must be at most 32 characters")]
+    fn test_nickname_length_is_bounded() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.set_nickname(token_id, "x".repeat(MAX_NICKNAME_LEN + 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "You are not the Token owner")]
+    fn test_only_token_owner_sets_nickname() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.set_nickname(token_id, "Sparky".to_string());
+    }
+
+    #[test]
+    fn test_tokens_excluding_eggs_lists_only_evolved() {
+        let (mut context, mut contract) = setup_contract();
+        let eggs: Vec<TokenId> = (0..4).map(|_| mint_egg(&mut context, &mut contract, accounts(1))).collect();
+        let other_egg = mint_egg(&mut context, &mut contract, accounts(2));
+
+        let first = evolve(&mut context, &mut contract, accounts(1), eggs[0].clone());
+        let second = evolve(&mut context, &mut contract, accounts(1), eggs[1].clone());
+        let second = evolve(&mut context, &mut contract, accounts(1), second);
+        let other = evolve(&mut context, &mut contract, accounts(2), other_egg);
+
+        let ids = |tokens: Vec<Token>| tokens.into_iter().map(|token| token.token_id).collect::<Vec<TokenId>>();
+        assert_eq!(ids(contract.nft_tokens_excluding_eggs(None, None)), vec![first.clone(), other.clone(), second.clone()]);
+        assert_eq!(ids(contract.nft_tokens_excluding_eggs(Some(U128(1)), Some(1))), vec![other.clone()]);
+        assert_eq!(ids(contract.nft_tokens_excluding_eggs(Some(U128(2)), None)), vec![second.clone()]);
+        assert!(contract.nft_tokens_excluding_eggs(Some(U128(3)), None).is_empty());
+
+        let owned = ids(contract.nft_tokens_for_owner_excluding_eggs(accounts(1).to_string(), None, None));
+        assert_eq!(owned.len(), 2);
+        assert!(owned.contains(&first) && owned.contains(&second));
+        assert!(!owned.contains(&eggs[2]) && !owned.contains(&eggs[3]));
+    }
+
+    #[test]
+    fn test_evolve_leaves_no_entries_for_consumed_id() {
+        let (mut context, mut contract) = setup_contract();
+        let egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_approve(egg_id.clone(), accounts(3), None);
+        contract.set_co_owners(egg_id.clone(), vec![accounts(2)]);
+        contract.set_nickname(egg_id.clone(), "Sparky".to_string());
+        testing_env!(context.predecessor_account_id(accounts(0)).attached_deposit(0).build());
+        contract.propose_token_rescue(egg_id.clone(), accounts(2));
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), egg_id.clone());
+
+        assert!(contract.tokens.owner_by_id.get(&egg_id).is_none());
+        assert!(contract.tokens.token_metadata_by_id.as_ref().unwrap().get(&egg_id).is_none());
+        assert!(contract.tokens.approvals_by_id.as_ref().unwrap().get(&egg_id).is_none());
+        assert!(contract.tokens.next_approval_id_by_id.as_ref().unwrap().get(&egg_id).is_none());
+        assert!(contract.egg_per_token_id.get(&egg_id).is_none());
+        assert!(contract.level_per_token_id.get(&egg_id).is_none());
+        assert!(contract.tokens_per_species.values().all(|set| !set.contains(&egg_id)));
+        assert!(contract.tokens_per_level.values().all(|set| !set.contains(&egg_id)));
+        assert!(!contract.token_ids_for_owner(accounts(1).to_string(), None, None).contains(&egg_id));
+        assert!(contract.xp_per_token_id.get(&egg_id).is_none());
+        assert!(contract.approval_expiry_by_id.get(&egg_id).is_none());
+        assert!(contract.co_owners_per_token.get(&egg_id).is_none());
+        assert!(contract.rescue_per_token.get(&egg_id).is_none());
+        assert!(contract.nickname_per_token.get(&egg_id).is_none());
+        assert!(contract.branch_per_token.get(&egg_id).is_none());
+        assert!(contract.locked_tokens.get(&egg_id).is_none());
+
+        assert_eq!(contract.evolution_history(evolved_id.clone()), vec![egg_id, evolved_id]);
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_nft_tokens_by_level_follows_evolve() {
+        let (mut context, mut contract) = setup_contract();
+
+        let egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let other_egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+        assert_eq!(contract.nft_tokens_by_level(0, None, None).len(), 2);
+        assert!(contract.nft_tokens_by_level(1, None, None).is_empty());
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), egg_id.clone());
+
+        let eggs: Vec<TokenId> = contract
+            .nft_tokens_by_level(0, None, None)
+            .into_iter()
+            .map(|token| token.token_id)
+            .collect();
+        assert_eq!(eggs, vec![other_egg_id]);
+
+        let monsters: Vec<TokenId> = contract
+            .nft_tokens_by_level(1, None, None)
+            .into_iter()
+            .map(|token| token.token_id)
+            .collect();
+        assert_eq!(monsters, vec![evolved_id]);
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_supply_per_level_shifts_on_evolve_and_burn() {
+        let (mut context, mut contract) = setup_contract();
+        assert!(contract.supply_per_level().is_empty());
+
+        let first = mint_egg(&mut context, &mut contract, accounts(1));
+        let second = mint_egg(&mut context, &mut contract, accounts(1));
+        mint_egg(&mut context, &mut contract, accounts(2));
+        assert_eq!(contract.supply_per_level(), vec![(0, U128(3))]);
+
+        let first = evolve(&mut context, &mut contract, accounts(1), first);
+        evolve(&mut context, &mut contract, accounts(1), second);
+        assert_eq!(contract.supply_per_level(), vec![(0, U128(1)), (1, U128(2))]);
+
+        let first = evolve(&mut context, &mut contract, accounts(1), first);
+        assert_eq!(contract.supply_per_level(), vec![(0, U128(1)), (1, U128(1)), (2, U128(1))]);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        contract.nft_burn(first);
+        assert_eq!(contract.supply_per_level(), vec![(0, U128(1)), (1, U128(1))]);
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_burn_refunds_freed_storage() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let species = contract.egg_per_token_id.get(&token_id).unwrap();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        let storage_before = env::storage_usage();
+        contract.nft_burn(token_id.clone());
+        let storage_freed = storage_before - env::storage_usage();
+
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        let event: Value = serde_json::from_str(logs[0].strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+        assert_eq!(event["event"], "nft_burn");
+        assert_eq!(event["data"][0]["owner_id"], accounts(1).to_string());
+        assert_eq!(event["data"][0]["token_ids"], serde_json::json!([token_id.clone()]));
+
+        assert!(storage_freed > 0);
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(receipts.contains(&format!("\"receiver_id\":\"{}\"", accounts(1))));
+        assert!(receipts.contains(&format!(
+            "\"deposit\":{}",
+            env::storage_byte_cost() * Balance::from(storage_freed)
+        )));
+
+        assert!(contract.nft_token(token_id).is_none());
+        assert!(contract.nft_tokens_by_species(species, None, None).is_empty());
+        assert!(contract.nft_tokens_by_level(0, None, None).is_empty());
+        assert!(contract.nft_tokens_for_owner(accounts(1).to_string(), None, Some(10)).is_empty());
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_burn_many_burns_whole_batch_with_one_event() {
+        let (mut context, mut contract) = setup_contract();
+        let first = mint_egg(&mut context, &mut contract, accounts(1));
+        let second = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        let storage_before = env::storage_usage();
+        contract.nft_burn_many(vec![first.clone(), second.clone()]);
+        let storage_freed = storage_before - env::storage_usage();
+
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        let event: Value = serde_json::from_str(logs[0].strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+        assert_eq!(event["event"], "nft_burn");
+        assert_eq!(event["data"][0]["token_ids"], serde_json::json!([first.clone(), second.clone()]));
+
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(receipts.contains(&format!(
+            "\"deposit\":{}",
+            env::storage_byte_cost() * Balance::from(storage_freed)
+        )));
+        assert!(contract.nft_token(first).is_none());
+        assert!(contract.nft_token(second).is_none());
+        assert_eq!(contract.nft_supply_for_owner(accounts(1).to_string()), U128(0));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_burn_many_with_foreign_token_burns_nothing() {
+        let (mut context, mut contract) = setup_contract();
+        let own = mint_egg(&mut context, &mut contract, accounts(1));
+        let foreign = mint_egg(&mut context, &mut contract, accounts(2));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            contract.nft_burn_many(vec![own.clone(), foreign.clone()]);
+        }));
+        assert!(result.is_err());
+        assert!(contract.nft_token(own).is_some());
+        assert!(contract.nft_token(foreign).is_some());
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_owner_set_key_is_stable_across_operations() {
+        let (mut context, mut contract) = setup_contract();
+        let owner_set = |index: usize| UnorderedSet::<TokenId>::new(tokens_per_owner_key(&accounts(index).to_string()));
+
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        assert!(owner_set(1).contains(&token_id));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(accounts(2), token_id.clone(), None, None);
+        assert!(!owner_set(1).contains(&token_id));
+        assert!(owner_set(2).contains(&token_id));
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(2), token_id.clone());
+        assert!(!owner_set(2).contains(&token_id));
+        assert!(owner_set(2).contains(&evolved_id));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        contract.nft_burn(evolved_id.clone());
+        assert!(!owner_set(2).contains(&evolved_id));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_owner_pages_cost_the_same_wherever_they_start() {
+        let (mut context, mut contract) = setup_contract();
+        for _ in 0..300 {
+            mint_egg(&mut context, &mut contract, accounts(1));
+        }
+        let owner = accounts(1).to_string();
+
+        let page_gas = |contract: &Contract, from_index: u128| {
+            testing_env!(context.clone().prepaid_gas(300_000_000_000_000).build());
+            let page = contract.nft_tokens_for_owner(owner.clone(), Some(U128(from_index)), Some(10));
+            assert_eq!(page.len(), 10);
+            env::used_gas()
+        };
+        let first_page = page_gas(&contract, 0);
+        let last_page = page_gas(&contract, 290);
+        assert!(last_page < first_page * 2, "paging from 290 cost {} vs {}", last_page, first_page);
+        assert!(last_page < 50_000_000_000_000);
+
+        assert!(contract.nft_tokens_for_owner(owner.clone(), Some(U128(300)), None).is_empty());
+        assert!(contract.nft_tokens_for_owner(owner, None, Some(0)).is_empty());
+    }
+
+    fn fund_redemptions(context: &mut VMContextBuilder, contract: &mut Contract, pool: Balance, per_egg: Balance) {
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(pool)
+            .build());
+        contract.fund_redemption_pool();
+        contract.set_redemption_amount(0, U128(per_egg));
+    }
+
+    #[test]
+    fn test_burn_to_redeem_pays_from_pool() {
+        let (mut context, mut contract) = setup_contract();
+        fund_redemptions(&mut context, &mut contract, 5 * MINIMUM_EARLY_DEPOSIT, MINIMUM_EARLY_DEPOSIT);
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        let storage_before = env::storage_usage();
+        assert_eq!(contract.burn_to_redeem(token_id.clone()), U128(MINIMUM_EARLY_DEPOSIT));
+        let storage_freed = storage_before - env::storage_usage();
+
+        let payout = MINIMUM_EARLY_DEPOSIT + env::storage_byte_cost() * Balance::from(storage_freed);
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(receipts.contains(&format!("\"receiver_id\":\"{}\"", accounts(1))));
+        assert!(receipts.contains(&format!("\"deposit\":{}", payout)));
+        assert_eq!(contract.redemption_pool(), U128(4 * MINIMUM_EARLY_DEPOSIT));
+        assert!(contract.nft_token(token_id).is_none());
+        contract.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot cover this redemption")]
+    fn test_burn_to_redeem_rejects_underfunded_pool() {
+        let (mut context, mut contract) = setup_contract();
+        fund_redemptions(&mut context, &mut contract, MINIMUM_EARLY_DEPOSIT, 2 * MINIMUM_EARLY_DEPOSIT);
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.burn_to_redeem(token_id);
+    }
+
+    #[test]
+    fn test_owner_page_carries_total() {
+        let (mut context, mut contract) = setup_contract();
+        for _ in 0..5 {
+            mint_egg(&mut context, &mut contract, accounts(1));
+        }
+        let owner = accounts(1).to_string();
+
+        let page = contract.nft_tokens_for_owner_page(owner.clone(), Some(U128(2)), Some(2));
+        assert_eq!(page.total, contract.nft_supply_for_owner(owner.clone()));
+        assert_eq!(page.total, U128(5));
+        let page_ids: Vec<TokenId> = page.tokens.into_iter().map(|token| token.token_id).collect();
+        assert_eq!(page_ids, contract.token_ids_for_owner(owner.clone(), Some(U128(2)), Some(2)));
+        assert_eq!(page_ids.len(), 2);
+
+        let past_end = contract.nft_tokens_for_owner_page(owner, Some(U128(5)), None);
+        assert_eq!(past_end.total, U128(5));
+        assert!(past_end.tokens.is_empty());
+    }
+
+    #[test]
+    fn test_co_owners_set_and_cleared_on_transfer() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.set_co_owners(token_id.clone(), vec![accounts(3), accounts(4)]);
+        assert_eq!(
+            contract.co_owners(token_id.clone()),
+            vec![accounts(3).to_string(), accounts(4).to_string()]
+        );
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.nft_transfer(accounts(2), token_id.clone(), None, None);
+        assert!(contract.co_owners(token_id).is_empty());
+        contract.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "You are not the Token owner")]
+    fn test_co_owners_set_by_owner_only() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.set_co_owners(token_id, vec![accounts(3)]);
+    }
+
+    #[test]
+    fn test_transfer_to_contract_account_schedules_no_call() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let vault: ValidAccountId = "vault.nearmon.testnet".try_into().unwrap();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(vault.clone(), token_id.clone(), None, None);
+
+        assert_eq!(contract.nft_token(token_id.clone()).unwrap().owner_id, vault.to_string());
+        assert_eq!(contract.token_ids_for_owner(vault.to_string(), None, None), vec![token_id]);
+        assert!(get_created_receipts().is_empty());
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_admin_reassign_token_moves_stuck_token_after_timelock() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        contract.nft_approve(token_id.clone(), accounts(3), None);
+
+        testing_env!(context.predecessor_account_id(accounts(0)).attached_deposit(0).build());
+        let ready_at = contract.propose_token_rescue(token_id.clone(), accounts(2));
+
+        testing_env!(context.block_timestamp(ready_at.0 * 1000000).build());
+        contract.admin_reassign_token(token_id.clone(), accounts(2));
+
+        assert_eq!(contract.nft_token(token_id.clone()).unwrap().owner_id, accounts(2).to_string());
+        assert!(contract.token_ids_for_owner(accounts(1).to_string(), None, None).is_empty());
+        assert_eq!(contract.token_ids_for_owner(accounts(2).to_string(), None, None), vec![token_id.clone()]);
+        assert!(contract.approved_accounts(token_id.clone()).is_empty());
+        assert!(contract.get_token_rescue(token_id.clone()).is_none());
+        assert_owner_counts_match(&contract);
+        contract.assert_invariants();
+
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        let event: Value = serde_json::from_str(logs[0].strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+        assert_eq!(event["event"], "nft_transfer");
+        assert_eq!(event["data"][0]["old_owner_id"], accounts(1).to_string());
+        assert_eq!(event["data"][0]["new_owner_id"], accounts(2).to_string());
+        assert_eq!(event["data"][0]["authorized_id"], accounts(0).to_string());
+        assert_eq!(event["data"][0]["memo"], "admin rescue");
+    }
+
+    #[test]
+    #[should_panic(expected = "Rescue cannot run until")]
+    fn test_admin_reassign_token_waits_for_timelock() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context.predecessor_account_id(accounts(0)).attached_deposit(0).build());
+        contract.propose_token_rescue(token_id.clone(), accounts(2));
+        contract.admin_reassign_token(token_id, accounts(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "No rescue is proposed for this token and receiver")]
+    fn test_token_owner_can_cancel_rescue() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context.predecessor_account_id(accounts(0)).attached_deposit(0).build());
+        let ready_at = contract.propose_token_rescue(token_id.clone(), accounts(2));
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.cancel_token_rescue(token_id.clone());
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .block_timestamp(ready_at.0 * 1000000)
+            .build());
+        contract.admin_reassign_token(token_id, accounts(2));
+    }
+
+    #[test]
+    fn test_transfer_discards_pending_rescue() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context.predecessor_account_id(accounts(0)).attached_deposit(0).build());
+        contract.propose_token_rescue(token_id.clone(), accounts(2));
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        contract.nft_transfer(accounts(3), token_id.clone(), None, None);
+        assert!(contract.get_token_rescue(token_id).is_none());
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_transfer_to_new_owner_does_not_require_storage_deposit() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(accounts(2), token_id.clone(), None, None);
+
+        assert_eq!(contract.nft_token(token_id).unwrap().owner_id, accounts(2).to_string());
+        assert!(contract.nft_tokens_for_owner(accounts(1).to_string(), None, Some(10)).is_empty());
+        contract.assert_invariants();
+    }
+
+    fn require_item_for_level_one(context: &mut VMContextBuilder, contract: &mut Contract) {
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_required_item(1, Some((accounts(5), U128(10))));
+    }
+
+    #[test]
+    fn test_evolve_consumes_deposited_item() {
+        let (mut context, mut contract) = setup_contract();
+        require_item_for_level_one(&mut context, &mut contract);
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context.predecessor_account_id(accounts(5)).build());
+        let unused = contract.ft_on_transfer(accounts(1), U128(15), EVOLVE_ITEM_MSG.to_string());
+        assert!(matches!(unused, PromiseOrValue::Value(U128(0))));
+        assert_eq!(contract.item_balance(accounts(1).to_string(), accounts(5).to_string()), U128(15));
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), token_id);
+        assert_eq!(contract.level_per_token(evolved_id), 1);
+        assert_eq!(contract.item_balance(accounts(1).to_string(), accounts(5).to_string()), U128(5));
+    }
+
+    #[test]
+    #[should_panic(expected = "Evolving needs 10 of")]
+    fn test_evolve_without_item_fails() {
+        let (mut context, mut contract) = setup_contract();
+        require_item_for_level_one(&mut context, &mut contract);
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context.predecessor_account_id(accounts(5)).build());
+        contract.ft_on_transfer(accounts(1), U128(9), EVOLVE_ITEM_MSG.to_string());
+        evolve(&mut context, &mut contract, accounts(1), token_id);
+    }
+
+    #[test]
+    fn test_item_transfer_without_evolve_intent_is_returned() {
+        let (mut context, mut contract) = setup_contract();
+        require_item_for_level_one(&mut context, &mut contract);
+
+        testing_env!(context.predecessor_account_id(accounts(5)).build());
+        let unused = contract.ft_on_transfer(accounts(1), U128(10), "".to_string());
+        assert!(matches!(unused, PromiseOrValue::Value(U128(10))));
+
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        let unused = contract.ft_on_transfer(accounts(1), U128(10), EVOLVE_ITEM_MSG.to_string());
+        assert!(matches!(unused, PromiseOrValue::Value(U128(10))));
+        assert_eq!(contract.item_balance(accounts(1).to_string(), accounts(4).to_string()), U128(0));
+    }
+
+    #[test]
+    fn test_evolve_blockers_list_missing_item_until_deposited() {
+        let (mut context, mut contract) = setup_contract();
+        require_item_for_level_one(&mut context, &mut contract);
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let evolve_time = token_extra_of(&contract, token_id.clone()).evolve_at;
+
+        testing_env!(context.predecessor_account_id(accounts(5)).block_timestamp(evolve_time * 1000000).build());
+        contract.ft_on_transfer(accounts(1), U128(9), EVOLVE_ITEM_MSG.to_string());
+        assert_eq!(
+            contract.evolve_blockers(token_id.clone()),
+            vec![format!("Evolving needs 10 of {}", accounts(5))]
+        );
+
+        contract.ft_on_transfer(accounts(1), U128(1), EVOLVE_ITEM_MSG.to_string());
+        assert!(contract.evolve_blockers(token_id).is_empty());
+    }
+
+    #[test]
+    fn test_withdraw_item_returns_unspent_deposit() {
+        let (mut context, mut contract) = setup_contract();
+        require_item_for_level_one(&mut context, &mut contract);
+
+        testing_env!(context.predecessor_account_id(accounts(5)).build());
+        contract.ft_on_transfer(accounts(1), U128(15), EVOLVE_ITEM_MSG.to_string());
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        contract.withdraw_item(accounts(5), Some(U128(6)));
+        assert_eq!(contract.item_balance(accounts(1).to_string(), accounts(5).to_string()), U128(9));
+
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(receipts.contains("ft_transfer"));
+        assert!(receipts.contains("resolve_item_withdraw"));
+        assert!(receipts.contains(&format!("\"receiver_id\":\"{}\"", accounts(5))));
+
+        testing_env_with_promise_results(context.predecessor_account_id(accounts(0)).build(), PromiseResult::Failed);
+        assert!(!contract.resolve_item_withdraw(accounts(1).to_string(), accounts(5).to_string(), U128(6)));
+        assert_eq!(contract.item_balance(accounts(1).to_string(), accounts(5).to_string()), U128(15));
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        contract.withdraw_item(accounts(5), None);
+        assert_eq!(contract.item_balance(accounts(1).to_string(), accounts(5).to_string()), U128(0));
+        assert!(contract.item_balances.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "E083: Item balance is only 5")]
+    fn test_withdraw_item_beyond_balance_fails() {
+        let (mut context, mut contract) = setup_contract();
+        require_item_for_level_one(&mut context, &mut contract);
+
+        testing_env!(context.predecessor_account_id(accounts(5)).build());
+        contract.ft_on_transfer(accounts(1), U128(5), EVOLVE_ITEM_MSG.to_string());
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        contract.withdraw_item(accounts(5), Some(U128(6)));
+    }
+
+    #[test]
+    fn test_evolve_without_msg_does_not_call_game_contract() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), token_id);
+
+        assert_eq!(contract.level_per_token(evolved_id), 1);
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(!receipts.contains("on_nft_evolve"));
+    }
+
+    #[test]
+    fn test_evolve_with_msg_notifies_game_contract() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_game_contract(Some(accounts(4)));
+
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let evolve_time = token_extra_of(contract, token_id.clone()).evolve_at;
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(EVOLVE_DEPOSIT)
+            .block_timestamp(evolve_time * 1000000)
+            .build());
+        contract.nft_evolve(token_id, Some("quest-42".to_string()), None, None);
+        let evolved_id = contract.current_token_id.to_string();
+
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(receipts.contains("on_nft_evolve"));
+        assert!(receipts.contains("nft_resolve_evolve"));
+        assert!(receipts.contains(&format!("\"receiver_id\":\"{}\"", accounts(4))));
+        assert_eq!(contract.level_per_token(evolved_id.clone()), 1);
+
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Failed,
+        );
+        assert!(!contract.nft_resolve_evolve(accounts(1).to_string(), evolved_id.clone()));
+        assert_eq!(contract.level_per_token(evolved_id), 1);
+    }
+
+    #[test]
+    fn test_mint_notifies_registry_contract() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_registry_contract(Some(accounts(4)));
+
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let species = contract.egg_per_token_id.get(&token_id).unwrap();
+
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(receipts.contains("on_nft_minted"));
+        assert!(receipts.contains(&format!("\"receiver_id\":\"{}\"", accounts(4))));
+        assert!(receipts.contains(&format!("\\\"owner_id\\\":\\\"{}\\\"", accounts(1))));
+        assert!(receipts.contains(&format!("\\\"token_id\\\":\\\"{}\\\"", token_id)));
+        assert!(receipts.contains(&format!("\\\"species\\\":{}", species)));
+    }
+
+    #[test]
+    fn test_treasury_receives_its_share_of_the_mint_fee() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_treasury(Some(accounts(4)), 2_500);
+        assert_eq!(contract.get_treasury(), Some((accounts(4).to_string(), 2_500)));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        let storage_before = env::storage_usage();
+        contract.nft_mint_egg(accounts(1).to_string(), None);
+        let storage_used = env::storage_usage() - storage_before;
+
+        let surplus = MINIMUM_EARLY_DEPOSIT - env::storage_byte_cost() * Balance::from(storage_used);
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(receipts.contains(&format!("\"receiver_id\":\"{}\"", accounts(4))));
+        assert!(receipts.contains(&format!("\"deposit\":{}", surplus / 4)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Basis points cannot exceed 10000")]
+    fn test_treasury_bps_bounded() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_treasury(Some(accounts(4)), MAX_BPS + 1);
+    }
+
+    #[test]
+    fn test_mint_without_registry_schedules_no_notification() {
+        let (mut context, mut contract) = setup_contract();
+        mint_egg(&mut context, &mut contract, accounts(1));
+
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(!receipts.contains("on_nft_minted"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Game contract is not configured")]
+    fn test_evolve_with_msg_requires_game_contract() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(EVOLVE_DEPOSIT)
+            .build());
+        contract.nft_evolve(token_id, Some("quest-42".to_string()), None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Token metadata does not match its level")]
+    fn test_evolve_refuses_level_metadata_mismatch() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        contract.level_per_token_id.insert(&token_id, &2);
+
+        evolve(&mut context, &mut contract, accounts(1), token_id);
+    }
+
+    #[test]
+    fn test_minted_at_survives_evolve() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.block_timestamp(1_000 * 1000000).build());
+        let egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+        assert_eq!(contract.minted_at(egg_id.clone()), Some(U64(1_000)));
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), egg_id);
+        let issued_at = contract.nft_token(evolved_id.clone()).unwrap().metadata.unwrap().issued_at.unwrap();
+
+        assert_eq!(contract.minted_at(evolved_id), Some(U64(1_000)));
+        assert_ne!(issued_at, "1000".to_string());
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_minted_at_unknown_token() {
+        let (_, contract) = setup_contract();
+        assert!(contract.minted_at("404".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_evolve_to_two_stages() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let evolve_at = token_extra_of(&contract, token_id.clone()).evolve_at;
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(EVOLVE_DEPOSIT * 2)
+            .block_timestamp((evolve_at + EVOLVE_COOLDOWN_MS) * 1000000)
+            .build());
+        let evolved_id = contract.nft_evolve_to(token_id.clone(), 2);
+
+        assert_eq!(contract.level_per_token(evolved_id.clone()), 2);
+        assert_eq!(contract.owner_of(evolved_id.clone()), Some(accounts(1).to_string()));
+        let history = contract.evolution_history(evolved_id.clone());
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0], token_id);
+
+        let logs = get_logs();
+        assert!(logs.iter().any(|log| log.contains("\"event\":\"nft_burn\"") && log.contains(&format!("\"{}\"", history[1]))));
+        assert!(logs.iter().any(|log| log.contains("\"event\":\"nft_mint\"") && log.contains(&format!("\"{}\"", evolved_id))));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "Stage 2 is not ready until")]
+    fn test_evolve_to_blocked_by_cumulative_cooldown() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let evolve_at = token_extra_of(&contract, token_id.clone()).evolve_at;
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(EVOLVE_DEPOSIT * 2)
+            .block_timestamp((evolve_at + EVOLVE_COOLDOWN_MS - 1) * 1000000)
+            .build());
+        contract.nft_evolve_to(token_id, 2);
+    }
+
+    #[test]
+    fn test_evolve_and_transfer_gifts_evolved_token() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let evolve_at = token_extra_of(&contract, token_id.clone()).evolve_at;
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(EVOLVE_DEPOSIT)
+            .block_timestamp(evolve_at * 1000000)
+            .build());
+        let evolved_id = contract.evolve_and_transfer(token_id.clone(), accounts(2));
+
+        assert_eq!(contract.owner_of(evolved_id.clone()), Some(accounts(2).to_string()));
+        assert_eq!(contract.owner_of(token_id.clone()), None);
+        assert_eq!(contract.token_ids_for_owner(accounts(2).to_string(), None, None), vec![evolved_id.clone()]);
+        assert!(contract.token_ids_for_owner(accounts(1).to_string(), None, None).is_empty());
+        assert_eq!(contract.total_owners(), 1);
+
+        let logs = get_logs();
+        assert!(logs.iter().any(|log| log.contains("\"event\":\"nft_burn\"") && log.contains(&format!("\"{}\"", token_id))));
+        assert!(logs.iter().any(|log| log.contains("\"event\":\"nft_mint\"") && log.contains(&format!("\"{}\"", evolved_id))));
+        assert!(logs.iter().any(|log| log.contains("\"event\":\"nft_transfer\"") && log.contains(&format!("\"{}\"", evolved_id))));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "You are not the Token owner")]
+    fn test_evolve_and_transfer_by_non_owner_fails() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let evolve_at = token_extra_of(&contract, token_id.clone()).evolve_at;
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(EVOLVE_DEPOSIT)
+            .block_timestamp(evolve_at * 1000000)
+            .build());
+        contract.evolve_and_transfer(token_id, accounts(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "The evolve time is not fullfiled")]
+    fn test_evolve_and_transfer_before_cooldown_fails() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let evolve_at = token_extra_of(&contract, token_id.clone()).evolve_at;
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(EVOLVE_DEPOSIT)
+            .block_timestamp((evolve_at - 1) * 1000000)
+            .build());
+        contract.evolve_and_transfer(token_id, accounts(2));
+    }
+
+    fn setup_branches(context: &mut VMContextBuilder, contract: &mut Contract) -> (TokenId, TokenId) {
+        let forms = vec!["Day Form", "Night Form"]
+            .into_iter()
+            .map(|title| {
+                let mut form = sample_token_metadata();
+                form.title = Some(title.to_string());
+                form
+            })
+            .collect();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_evolve_branches(1, 1, forms);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(MINIMUM_EARLY_DEPOSIT).build());
+        let first = contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![0]));
+        let second = contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![0]));
+        (first, second)
+    }
+
+    fn evolve_branch(context: &mut VMContextBuilder, contract: &mut Contract, token_id: TokenId, branch: Option<u64>) -> TokenId {
+        let evolve_time = token_extra_of(contract, token_id.clone()).evolve_at;
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(EVOLVE_DEPOSIT)
+            .block_timestamp(evolve_time * 1000000)
+            .build());
+        contract.nft_evolve(token_id, None, branch, None);
+        contract.current_token_id.to_string()
+    }
+
+    #[test]
+    fn test_evolve_down_different_branches() {
+        let (mut context, mut contract) = setup_contract();
+        let (first, second) = setup_branches(&mut context, &mut contract);
+
+        let day = evolve_branch(&mut context, &mut contract, first, None);
+        let night = evolve_branch(&mut context, &mut contract, second, Some(1));
+
+        assert_eq!(contract.token_metadata(day.clone()).unwrap().title, Some("Day Form".to_string()));
+        assert_eq!(contract.token_metadata(night.clone()).unwrap().title, Some("Night Form".to_string()));
+        assert_eq!(contract.level_per_token(day.clone()), 1);
+        assert_eq!(contract.level_per_token(night.clone()), 1);
+
+        // Branch forms still count as valid metadata for their level.
+        contract.assert_metadata_matches_level(&day);
+        contract.assert_metadata_matches_level(&night);
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_regenerate_metadata_keeps_branch_form() {
+        let (mut context, mut contract) = setup_contract();
+        let (first, second) = setup_branches(&mut context, &mut contract);
+        let day = evolve_branch(&mut context, &mut contract, first, None);
+        let night = evolve_branch(&mut context, &mut contract, second, Some(1));
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.regenerate_metadata(day.clone());
+        contract.regenerate_metadata(night.clone());
+
+        assert_eq!(contract.token_metadata(day).unwrap().title, Some("Day Form".to_string()));
+        assert_eq!(contract.token_metadata(night.clone()).unwrap().title, Some("Night Form".to_string()));
+
+        let evolved = evolve_branch(&mut context, &mut contract, night, None);
+        assert!(contract.branch_per_token.get(&evolved).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Branch is not configured for this species and level")]
+    fn test_evolve_rejects_unknown_branch() {
+        let (mut context, mut contract) = setup_contract();
+        let (first, _) = setup_branches(&mut context, &mut contract);
+
+        evolve_branch(&mut context, &mut contract, first, Some(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Branch is not configured for this species and level")]
+    fn test_evolve_rejects_branch_without_table() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        evolve_branch(&mut context, &mut contract, token_id, Some(1));
+    }
+
+    fn transfer_event_log() -> String {
+        get_logs()
+            .into_iter()
+            .find(|log| log.starts_with("EVENT_JSON:") && log.contains("\"event\":\"nft_transfer\""))
+            .expect("nft_transfer event was not emitted")
+    }
+
+    #[test]
+    fn test_transfer_event_includes_memo() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(accounts(2), token_id.clone(), None, Some("birthday gift".to_string()));
+
+        let log = transfer_event_log();
+        assert!(log.contains("\"memo\":\"birthday gift\""));
+        assert!(log.contains(&format!("\"old_owner_id\":\"{}\"", accounts(1))));
+        assert!(log.contains(&format!("\"new_owner_id\":\"{}\"", accounts(2))));
+        assert!(!log.contains("authorized_id"));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_transfer_event_without_memo_omits_field() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(accounts(2), token_id, None, None);
+
+        assert!(!transfer_event_log().contains("memo"));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_transfer_call_forwards_memo() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer_call(accounts(2), token_id, None, Some("listing".to_string()), "sell".to_string());
+
+        assert!(transfer_event_log().contains("\"memo\":\"listing\""));
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(receipts.contains("nft_on_transfer"));
+        assert!(receipts.contains("nft_resolve_transfer"));
+        contract.assert_invariants();
+    }
+
+    fn transfer_call_and_resolve(result: PromiseResult) -> (Contract, TokenId, bool) {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        contract.nft_transfer_call(accounts(2), token_id.clone(), None, None, "play".to_string());
+
+        testing_env_with_promise_results(context.predecessor_account_id(accounts(0)).build(), result);
+        let transferred = contract.nft_resolve_transfer(
+            accounts(1).to_string(),
+            accounts(2).to_string(),
+            token_id.clone(),
+            None,
+        );
+        (contract, token_id, transferred)
+    }
+
+    fn assert_indexes_intact(contract: &Contract, token_id: &TokenId, owner_id: AccountId) {
+        let species = contract.egg_per_token_id.get(token_id).unwrap();
+        assert_eq!(contract.owner_of(token_id.clone()), Some(owner_id.clone()));
+        assert!(contract.token_ids_for_owner(owner_id, None, None).contains(token_id));
+        assert_eq!(contract.level_per_token_id.get(token_id), Some(0));
+        assert!(contract.tokens_per_species.get(&species).unwrap().contains(token_id));
+        assert!(contract.tokens_per_level.get(&0).unwrap().contains(token_id));
+    }
+
+    #[test]
+    fn test_resolve_transfer_returns_token_when_receiver_asks() {
+        let (contract, token_id, transferred) = transfer_call_and_resolve(PromiseResult::Successful(b"true".to_vec()));
+
+        assert!(!transferred);
+        assert_indexes_intact(&contract, &token_id, accounts(1).to_string());
+        assert_eq!(contract.nft_supply_for_owner(accounts(1).to_string()), U128(2));
+        assert_eq!(contract.nft_supply_for_owner(accounts(2).to_string()), U128(0));
+        assert_eq!(contract.total_owners(), 1);
+        assert!(get_logs().iter().any(|log| log.contains(&format!("\"old_owner_id\":\"{}\"", accounts(2)))));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_resolve_transfer_keeps_token_when_receiver_accepts() {
+        let (contract, token_id, transferred) = transfer_call_and_resolve(PromiseResult::Successful(b"false".to_vec()));
+
+        assert!(transferred);
+        assert_indexes_intact(&contract, &token_id, accounts(2).to_string());
+        assert_eq!(contract.nft_supply_for_owner(accounts(1).to_string()), U128(1));
+        assert_eq!(contract.nft_supply_for_owner(accounts(2).to_string()), U128(1));
+        assert_eq!(contract.total_owners(), 2);
+        assert!(get_logs().is_empty());
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_resolve_transfer_returns_token_when_receiver_fails() {
+        let (contract, token_id, transferred) = transfer_call_and_resolve(PromiseResult::Failed);
+
+        assert!(!transferred);
+        assert_indexes_intact(&contract, &token_id, accounts(1).to_string());
+        assert_eq!(contract.nft_supply_for_owner(accounts(1).to_string()), U128(2));
+        assert_eq!(contract.total_owners(), 1);
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_resolve_transfer_returns_last_token_to_owner_set() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        contract.nft_transfer_call(accounts(2), token_id.clone(), None, None, "play".to_string());
+        assert!(contract.nft_tokens_for_owner(accounts(1).to_string(), None, None).is_empty());
+
+        testing_env_with_promise_results(context.predecessor_account_id(accounts(0)).build(), PromiseResult::Failed);
+        assert!(!contract.nft_resolve_transfer(accounts(1).to_string(), accounts(2).to_string(), token_id.clone(), None));
+
+        let owned = contract.nft_tokens_for_owner(accounts(1).to_string(), None, None);
+        assert_eq!(owned.len(), 1);
+        assert_eq!(owned[0].token_id, token_id);
+        assert!(UnorderedSet::<TokenId>::new(tokens_per_owner_key(&accounts(1).to_string())).contains(&token_id));
+        assert!(contract.nft_tokens_for_owner(accounts(2).to_string(), None, None).is_empty());
+        contract.assert_invariants();
+    }
 
-    const DATA_IMAGE_SVG_PARAS_ICON: &str = "data:image/svg+xml,%3Csvg width='1080' height='1080' viewBox='0 0 1080 1080' fill='none' xmlns='http://www.w3.org/2000/svg'%3E%3Crect width='1080' height='1080' rx='10' fill='%230000BA'/%3E%3Cpath fill-rule='evenodd' clip-rule='evenodd' d='M335.238 896.881L240 184L642.381 255.288C659.486 259.781 675.323 263.392 689.906 266.718C744.744 279.224 781.843 287.684 801.905 323.725C827.302 369.032 840 424.795 840 491.014C840 557.55 827.302 613.471 801.905 658.779C776.508 704.087 723.333 726.74 642.381 726.74H468.095L501.429 896.881H335.238ZM387.619 331.329L604.777 369.407C614.008 371.807 622.555 373.736 630.426 375.513C660.02 382.193 680.042 386.712 690.869 405.963C704.575 430.164 711.428 459.95 711.428 495.321C711.428 530.861 704.575 560.731 690.869 584.932C677.163 609.133 648.466 621.234 604.777 621.234H505.578L445.798 616.481L387.619 331.329Z' fill='white'/%3E%3C/svg%3E";
+    #[test]
+    fn test_resolve_transfer_drops_no_set_when_last_token_stays() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
 
-    fn get_context(predecessor_account_id: ValidAccountId) -> VMContextBuilder {
-        let mut builder = VMContextBuilder::new();
-        builder
-            .current_account_id(accounts(0))
-            .signer_account_id(predecessor_account_id.clone())
-            .predecessor_account_id(predecessor_account_id);
-        builder
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        contract.nft_transfer_call(accounts(2), token_id.clone(), None, None, "play".to_string());
+
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Successful(b"false".to_vec()),
+        );
+        assert!(contract.nft_resolve_transfer(accounts(1).to_string(), accounts(2).to_string(), token_id, None));
+
+        assert!(!contract.tokens.tokens_per_owner.as_ref().unwrap().contains_key(&accounts(1).to_string()));
+        contract.assert_invariants();
     }
 
-    fn setup_contract() -> (VMContextBuilder, Contract) {
-        let mut context = VMContextBuilder::new();
+    fn evolve_with_deposit(context: &mut VMContextBuilder, contract: &mut Contract, token_id: TokenId, deposit: Balance) -> TokenId {
+        let evolve_time = token_extra_of(&contract, token_id.clone()).evolve_at;
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(deposit)
+            .block_timestamp(evolve_time * 1000000)
+            .build());
+        contract.nft_evolve(token_id, None, None, None);
+        contract.current_token_id.to_string()
+    }
+
+    #[test]
+    fn test_reduced_evolve_fee() {
+        let (mut context, mut contract) = setup_contract();
         testing_env!(context.predecessor_account_id(accounts(0)).build());
-        let contract = Contract::new_default_meta(accounts(0));
-        (context, contract)
+        contract.set_evolve_fee(U128(MINIMUM_EARLY_DEPOSIT / 10));
+        assert_eq!(contract.get_evolve_fee(), U128(MINIMUM_EARLY_DEPOSIT / 10));
+
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let evolved_id = evolve_with_deposit(&mut context, &mut contract, token_id, MINIMUM_EARLY_DEPOSIT / 5);
+
+        assert_eq!(contract.level_per_token(evolved_id), 1);
     }
 
-    fn sample_token_metadata() -> TokenMetadata {
-      TokenMetadata {
-        title: Some("Olympus Mons".into()),
-        description: Some("The tallest mountain in the charted solar system".into()),
-        media: None,
-        media_hash: None,
-        copies: Some(1u64),
-        issued_at: None,
-        expires_at: None,
-        starts_at: None,
-        updated_at: None,
-        extra: None,
-        reference: None,
-        reference_hash: None,
-      }
+    #[test]
+    #[should_panic(expected = "Must attach")]
+    fn test_evolve_below_fee_fails() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_evolve_fee(U128(MINIMUM_EARLY_DEPOSIT / 10));
+
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        evolve_with_deposit(&mut context, &mut contract, token_id, MINIMUM_EARLY_DEPOSIT / 20);
     }
 
     #[test]
-    fn test_new() {
-        let mut context = get_context(accounts(1));
-        testing_env!(context.build());
-        let contract = Contract::new(
-            accounts(1),
-            NFTContractMetadata {
-                spec: NFT_METADATA_SPEC.to_string(),
-                name: "Triple Triad".to_string(),
-                symbol: "TRIAD".to_string(),
-                icon: Some(DATA_IMAGE_SVG_PARAS_ICON.to_string()),
-                base_uri: Some("https://ipfs.fleek.co/ipfs/".to_string()),
-                reference: None,
-                reference_hash: None,
-            }
+    fn test_zero_evolve_fee_still_covers_storage() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_evolve_fee(U128(0));
+
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let deposit = MINIMUM_EARLY_DEPOSIT / 10;
+        let evolve_time = token_extra_of(&contract, token_id.clone()).evolve_at;
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(deposit)
+            .block_timestamp(evolve_time * 1000000)
+            .build());
+        let storage_before = env::storage_usage();
+        contract.nft_evolve(token_id, None, None, None);
+        let storage_used = env::storage_usage().saturating_sub(storage_before);
+        let evolved_id = contract.current_token_id.to_string();
+
+        assert_eq!(contract.level_per_token(evolved_id), 1);
+        let refund = deposit - env::storage_byte_cost() * Balance::from(storage_used);
+        let receipts = near_sdk::serde_json::to_string(&get_created_receipts()).unwrap();
+        assert!(receipts.contains(&format!("\"deposit\":{}", refund)));
+    }
+
+    #[test]
+    fn test_add_metadata_emits_template_added() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let template = sample_token_metadata();
+        contract.add_metadata("Monster_1".to_string(), template.clone());
+
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        let event: Value = serde_json::from_str(logs[0].strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+        assert_eq!(event["standard"], "nearmon");
+        assert_eq!(event["event"], "template_added");
+        assert_eq!(event["data"][0]["metadata_type"], "monster_1");
+        assert_eq!(
+            event["data"][0]["template_hash"],
+            serde_json::to_value(Base64VecU8(env::sha256(&template.try_to_vec().unwrap()))).unwrap()
         );
-        testing_env!(context.is_view(true).build());
-        assert_eq!(contract.get_owner(), accounts(1).to_string());
-        assert_eq!(contract.nft_metadata().base_uri.unwrap(), "https://ipfs.fleek.co/ipfs/".to_string());
-        assert_eq!(contract.nft_metadata().icon.unwrap(), DATA_IMAGE_SVG_PARAS_ICON.to_string());
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.add_metadata("monster_1".to_string(), template);
+        assert!(get_logs().is_empty());
+    }
+
+    #[test]
+    fn test_add_metadata_normalises_type_aliases() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.add_metadata("Egg".to_string(), sample_token_metadata());
+        contract.add_metadata("2".to_string(), sample_token_metadata());
+
+        assert_eq!(contract.metadata_per_type_list("monster_0".to_string()).len(), 1);
+        assert_eq!(contract.metadata_per_type_list("monster_2".to_string()).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Metadata type must be egg, monster_{level} or a level number")]
+    fn test_add_metadata_rejects_unknown_type() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.add_metadata("monstr_1".to_string(), sample_token_metadata());
+    }
+
+    #[test]
+    #[should_panic(expected = "Template needs a title")]
+    fn test_add_metadata_rejects_template_without_title() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut template = sample_token_metadata();
+        template.title = None;
+        contract.add_metadata("monster_1".to_string(), template);
+    }
+
+    #[test]
+    fn test_all_metadata_pages_across_types() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+
+        for title in &["Egg A", "Egg B"] {
+            let mut template = sample_token_metadata();
+            template.title = Some(title.to_string());
+            contract.add_metadata("monster_0".to_string(), template);
+        }
+        let mut template = sample_token_metadata();
+        template.title = Some("Monster A".into());
+        contract.add_metadata("monster_1".to_string(), template);
+
+        let all = contract.all_metadata(None, None);
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].0, "monster_0".to_string());
+        assert_eq!(all[1].0, "monster_0".to_string());
+        assert_eq!(all[2], ("monster_1".to_string(), contract.metadata_per_type_list("monster_1".to_string())[0].clone()));
+
+        let page = contract.all_metadata(Some(U128(1)), Some(1));
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].1.title, all[1].1.title);
+
+        assert_eq!(contract.all_metadata(Some(U128(2)), Some(5)).len(), 1);
+        assert!(contract.all_metadata(Some(U128(3)), None).is_empty());
+    }
+
+    #[test]
+    fn test_template_attrs_survive_mint_and_evolve() {
+        let (mut context, mut contract) = setup_contract();
+
+        let mut template = sample_token_metadata();
+        template.extra = Some("{\"element\":\"fire\",\"speed\":7}".into());
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.add_metadata("monster_0".to_string(), template);
+
+        let egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let egg_extra = token_extra_of(&contract, egg_id.clone());
+        let attrs = egg_extra.attrs.unwrap();
+        assert_eq!(attrs["element"], "fire");
+        assert_eq!(attrs["speed"], 7);
+        assert!(egg_extra.evolve_at > 0);
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), egg_id);
+        let attrs = token_extra_of(&contract, evolved_id).attrs.unwrap();
+        assert_eq!(attrs["element"], "fire");
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_legacy_timestamp_extra_still_parses() {
+        let extra = TokenExtra::parse("1650000000000");
+        assert_eq!(extra.evolve_at, 1650000000000);
+        assert!(extra.attrs.is_none());
+    }
+
+    #[test]
+    fn test_game_attrs_match_stored_state() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut template = sample_token_metadata();
+        template.extra = Some("{\"element\":\"fire\"}".to_string());
+        contract.add_metadata("monster_1".to_string(), template);
+
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let egg = contract.game_attrs(token_id.clone()).unwrap();
+        assert_eq!(egg.species, contract.egg_per_token_id.get(&token_id).unwrap());
+        assert_eq!(egg.level, 0);
+        assert_eq!(egg.evolve_at, U64(token_extra_of(&contract, token_id.clone()).evolve_at));
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), token_id.clone());
+        let monster = contract.game_attrs(evolved_id.clone()).unwrap();
+        assert_eq!(monster.species, egg.species);
+        assert_eq!(monster.level, 1);
+        assert_eq!(monster.evolve_at, U64(token_extra_of(&contract, evolved_id).evolve_at));
+        assert_eq!(monster.attrs, Some(near_sdk::serde_json::json!({ "element": "fire" })));
+
+        assert!(contract.game_attrs(token_id).is_none());
+    }
+
+    #[test]
+    fn test_evolved_token_lands_with_caller() {
+        let (mut context, mut contract) = setup_contract();
+        let egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+        mint_egg(&mut context, &mut contract, accounts(2));
+
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), egg_id.clone());
+
+        assert_eq!(contract.owner_of(evolved_id.clone()), Some(accounts(1).to_string()));
+        let owned: Vec<TokenId> = contract
+            .nft_tokens_for_owner(accounts(1).to_string(), None, None)
+            .into_iter()
+            .map(|token| token.token_id)
+            .collect();
+        assert_eq!(owned, vec![evolved_id]);
+        assert_eq!(contract.nft_tokens_for_owner(accounts(2).to_string(), None, None).len(), 1);
+        assert_eq!(contract.total_owners(), 2);
+        contract.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "You are not the Token owner")]
+    fn test_evolve_by_non_owner_fails() {
+        let (mut context, mut contract) = setup_contract();
+        let egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        evolve(&mut context, &mut contract, accounts(2), egg_id);
+    }
+
+    fn ready_to_evolve(context: &mut VMContextBuilder, contract: &Contract, token_id: TokenId) {
+        let evolve_time = token_extra_of(contract, token_id).evolve_at;
+        testing_env!(context.block_timestamp(evolve_time * 1000000).build());
+    }
+
+    #[test]
+    fn test_can_evolve_ready_token() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        ready_to_evolve(&mut context, &contract, token_id.clone());
+
+        assert!(contract.evolve_blockers(token_id.clone()).is_empty());
+        assert!(contract.can_evolve(token_id));
+    }
+
+    #[test]
+    fn test_evolve_blocked_by_missing_token() {
+        let (_, contract) = setup_contract();
+
+        assert_eq!(contract.evolve_blockers("404".to_string()), vec!["Token not found".to_string()]);
+        assert!(!contract.can_evolve("404".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Token metadata store is not configured")]
+    fn test_evolve_requires_metadata_store() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        contract.tokens.token_metadata_by_id = None;
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(EVOLVE_DEPOSIT)
+            .build());
+        contract.nft_evolve(token_id, None, None, None);
+    }
+
+    #[test]
+    fn test_evolve_retry_on_consumed_id_fails_cleanly() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let evolved_id = evolve(&mut context, &mut contract, accounts(1), token_id.clone());
+        let next_id = contract.next_token_id();
+        let supply = contract.supply_per_level();
+
+        let retry = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            contract.nft_evolve(token_id.clone(), None, None, None);
+        }));
+        let message = retry.unwrap_err().downcast::<String>().unwrap();
+        assert!(message.contains("Token no longer exists"), "{}", message);
+
+        assert_eq!(contract.next_token_id(), next_id);
+        assert_eq!(contract.supply_per_level(), supply);
+        assert_eq!(contract.token_ids_for_owner(accounts(1).to_string(), None, None), vec![evolved_id]);
+        contract.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "Token not found")]
+    fn test_evolve_unknown_id_is_not_found() {
+        let (mut context, mut contract) = setup_contract();
+        mint_egg(&mut context, &mut contract, accounts(1));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(EVOLVE_DEPOSIT)
+            .build());
+        contract.nft_evolve("99".to_string(), None, None, None);
+    }
+
+    #[test]
+    fn test_claim_hatch_turns_ready_egg_into_level_one() {
+        let (mut context, mut contract) = setup_contract();
+        let egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let ready_at = token_extra_of(&contract, egg_id.clone()).evolve_at;
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(EVOLVE_DEPOSIT)
+            .block_timestamp(ready_at * 1000000)
+            .build());
+        let hatched_id = contract.claim_hatch(egg_id.clone());
+
+        assert_eq!(contract.level_per_token(hatched_id.clone()), 1);
+        assert_eq!(contract.nft_token(hatched_id).unwrap().owner_id, accounts(1).to_string());
+        assert!(contract.nft_token(egg_id).is_none());
+        assert!(get_logs().iter().any(|log| log.contains("\"memo\":\"hatched\"")));
+    }
+
+    #[test]
+    #[should_panic(expected = "Egg not ready to hatch until")]
+    fn test_claim_hatch_rejects_egg_before_cooldown() {
+        let (mut context, mut contract) = setup_contract();
+        let egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(EVOLVE_DEPOSIT)
+            .build());
+        contract.claim_hatch(egg_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Token has already hatched")]
+    fn test_claim_hatch_rejects_hatched_token() {
+        let (mut context, mut contract) = setup_contract();
+        let egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let hatched_id = evolve(&mut context, &mut contract, accounts(1), egg_id);
+        contract.claim_hatch(hatched_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "E068: Token no longer exists")]
+    fn test_claim_hatch_rejects_consumed_egg() {
+        let (mut context, mut contract) = setup_contract();
+        let egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+        evolve(&mut context, &mut contract, accounts(1), egg_id.clone());
+        contract.claim_hatch(egg_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "This species is fully evolved")]
+    fn test_species_cap_blocks_evolve_past_max_level() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).attached_deposit(MINIMUM_EARLY_DEPOSIT).build());
+        let token_id = contract.nft_mint_egg_species(accounts(1).to_string(), 3);
+        contract.set_max_level_for_species(3, Some(2));
+
+        let token_id = evolve(&mut context, &mut contract, accounts(1), token_id);
+        let token_id = evolve(&mut context, &mut contract, accounts(1), token_id);
+        assert_eq!(contract.level_per_token(token_id.clone()), 2);
+        assert_eq!(contract.evolve_blockers(token_id.clone()), vec!["This species is fully evolved".to_string()]);
+
+        evolve(&mut context, &mut contract, accounts(1), token_id);
+    }
+
+    #[test]
+    fn test_uncapped_species_evolve_to_template_limit() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).attached_deposit(MINIMUM_EARLY_DEPOSIT).build());
+        let token_id = contract.nft_mint_egg_species(accounts(1).to_string(), 4);
+        contract.set_max_level_for_species(3, Some(2));
+        assert_eq!(contract.get_max_level_for_species(4), None);
+
+        let mut token_id = token_id;
+        for _ in 0..3 {
+            token_id = evolve(&mut context, &mut contract, accounts(1), token_id);
+        }
+        assert_eq!(contract.level_per_token(token_id), 3);
+    }
+
+    #[test]
+    fn test_evolve_blocked_by_cooldown() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let evolve_time = token_extra_of(&contract, token_id.clone()).evolve_at;
+
+        assert_eq!(
+            contract.evolve_blockers(token_id.clone()),
+            vec![format!("Evolve cooldown ends at {}", evolve_time)]
+        );
+        assert!(!contract.can_evolve(token_id));
+    }
+
+    #[test]
+    fn test_evolve_blocked_by_max_level() {
+        let (mut context, mut contract) = setup_contract();
+        let mut token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        for _ in 0..3 {
+            token_id = evolve(&mut context, &mut contract, accounts(1), token_id);
+        }
+        ready_to_evolve(&mut context, &contract, token_id.clone());
+
+        assert_eq!(
+            contract.evolve_blockers(token_id.clone()),
+            vec!["Token has reached its maximum level".to_string()]
+        );
+        assert!(!contract.can_evolve(token_id));
+    }
+
+    #[test]
+    fn test_evolve_blocked_by_lock() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = lock_by_staking_contract(&mut context, &mut contract);
+        ready_to_evolve(&mut context, &contract, token_id.clone());
+
+        assert_eq!(contract.evolve_blockers(token_id.clone()), vec!["Token is locked".to_string()]);
+        assert!(!contract.can_evolve(token_id));
+    }
+
+    #[test]
+    fn test_evolution_history_walks_back_to_egg() {
+        let (mut context, mut contract) = setup_contract();
+        let egg_id = mint_egg(&mut context, &mut contract, accounts(1));
+        let first_id = evolve(&mut context, &mut contract, accounts(1), egg_id.clone());
+        let second_id = evolve(&mut context, &mut contract, accounts(1), first_id.clone());
+
+        assert_eq!(
+            contract.evolution_history(second_id.clone()),
+            vec![egg_id.clone(), first_id.clone(), second_id]
+        );
+        assert_eq!(contract.evolution_history(first_id.clone()), vec![egg_id.clone(), first_id]);
+        assert_eq!(contract.evolution_history(egg_id.clone()), vec![egg_id]);
+    }
+
+    fn setup_recipe(context: &mut VMContextBuilder, contract: &mut Contract, inputs: Vec<(u64, u64)>) {
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.add_recipe(1, Recipe {
+            inputs: inputs
+                .into_iter()
+                .map(|(species, level)| RecipeInput { species, level })
+                .collect(),
+            output_species: 9,
+        });
+    }
+
+    fn mint_species_one_eggs(context: &mut VMContextBuilder, contract: &mut Contract, count: usize) -> Vec<TokenId> {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINIMUM_EARLY_DEPOSIT)
+            .build());
+        (0..count)
+            .map(|_| contract.internal_mint_egg(accounts(1).to_string(), &FixedRandomSource(vec![0])))
+            .collect()
+    }
+
+    #[test]
+    fn test_craft_burns_inputs_and_mints_output() {
+        let (mut context, mut contract) = setup_contract();
+        setup_recipe(&mut context, &mut contract, vec![(1, 0), (1, 0)]);
+        let inputs = mint_species_one_eggs(&mut context, &mut contract, 2);
+
+        let crafted_id = contract.craft(inputs.clone(), 1);
+
+        let events: Vec<Value> = get_logs()
+            .iter()
+            .filter_map(|log| log.strip_prefix("EVENT_JSON:"))
+            .map(|log| serde_json::from_str(log).unwrap())
+            .collect();
+        let burn = events.iter().find(|event| event["event"] == "nft_burn").unwrap();
+        assert_eq!(burn["data"][0]["owner_id"], accounts(1).to_string());
+        assert_eq!(burn["data"][0]["token_ids"], serde_json::json!(inputs));
+        let mint = events.iter().find(|event| event["event"] == "nft_mint").unwrap();
+        assert_eq!(mint["data"][0]["owner_id"], accounts(1).to_string());
+        assert_eq!(mint["data"][0]["token_ids"], serde_json::json!([crafted_id]));
+
+        for token_id in inputs {
+            assert!(contract.owner_of(token_id).is_none());
+        }
+        assert_eq!(contract.owner_of(crafted_id.clone()), Some(accounts(1).to_string()));
+        assert_eq!(contract.egg_per_token_id.get(&crafted_id).unwrap(), 9);
+        assert_eq!(contract.supply_for_species(1), U128(0));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_craft_with_mismatched_inputs_burns_nothing() {
+        let (mut context, mut contract) = setup_contract();
+        setup_recipe(&mut context, &mut contract, vec![(1, 0), (2, 0)]);
+        let inputs = mint_species_one_eggs(&mut context, &mut contract, 2);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            contract.craft(inputs.clone(), 1);
+        }));
+
+        assert!(result.is_err());
+        for token_id in inputs {
+            assert_eq!(contract.owner_of(token_id), Some(accounts(1).to_string()));
+        }
+        contract.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "Inputs do not match the recipe")]
+    fn test_craft_rejects_partial_match() {
+        let (mut context, mut contract) = setup_contract();
+        setup_recipe(&mut context, &mut contract, vec![(1, 0), (1, 0), (1, 0)]);
+        let inputs = mint_species_one_eggs(&mut context, &mut contract, 2);
+
+        contract.craft(inputs, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate token id in inputs")]
+    fn test_craft_rejects_duplicate_token_ids() {
+        let (mut context, mut contract) = setup_contract();
+        setup_recipe(&mut context, &mut contract, vec![(1, 0), (1, 0)]);
+        let inputs = mint_species_one_eggs(&mut context, &mut contract, 1);
+
+        contract.craft(vec![inputs[0].clone(), inputs[0].clone()], 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Batches must hold between 1 and 50 items")]
+    fn test_craft_rejects_oversized_batch() {
+        let (mut context, mut contract) = setup_contract();
+        setup_recipe(&mut context, &mut contract, vec![(1, 0)]);
+
+        let token_ids = (0..MAX_BATCH + 1).map(|id| id.to_string()).collect();
+        contract.craft(token_ids, 1);
+    }
+
+    fn lock_by_staking_contract(context: &mut VMContextBuilder, contract: &mut Contract) -> TokenId {
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.add_approved_locker(accounts(4));
+
+        let token_id = mint_egg(context, contract, accounts(1));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(0)
+            .build());
+        contract.lock_token(token_id.clone(), accounts(4));
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        token_id
+    }
+
+    #[test]
+    #[should_panic(expected = "Token is locked")]
+    fn test_locked_token_cannot_be_transferred() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = lock_by_staking_contract(&mut context, &mut contract);
+        assert!(contract.is_locked(token_id.clone()));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(accounts(2), token_id, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the locking contract can unlock")]
+    fn test_only_locking_contract_can_unlock() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = lock_by_staking_contract(&mut context, &mut contract);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.unlock_token(token_id);
+    }
+
+    #[test]
+    fn test_unlocked_token_can_be_transferred_again() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = lock_by_staking_contract(&mut context, &mut contract);
+
+        contract.unlock_token(token_id.clone());
+        assert!(!contract.is_locked(token_id.clone()));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(accounts(2), token_id.clone(), None, None);
+        assert_eq!(contract.owner_of(token_id), Some(accounts(2).to_string()));
+        contract.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "Locker is not approved")]
+    fn test_unapproved_locker_cannot_lock() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.lock_token(token_id, accounts(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "E035: Lock must be requested by the Token owner")]
+    fn test_locker_cannot_lock_on_owner_signature_alone() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.add_approved_locker(accounts(4));
+        let token_id = mint_egg(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context
+            .signer_account_id(accounts(1))
+            .predecessor_account_id(accounts(4))
+            .build());
+        contract.lock_token(token_id, accounts(4));
+    }
+
+    #[test]
+    fn test_evolve_into_registered_level_template() {
+        let (mut context, mut contract) = setup_contract();
+
+        let mut template = sample_token_metadata();
+        template.title = Some("Mega Dragon".into());
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.add_metadata("monster_4".to_string(), template);
+
+        let mut token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        for _ in 0..4 {
+            token_id = evolve(&mut context, &mut contract, accounts(1), token_id);
+        }
+
+        assert_eq!(contract.level_per_token(token_id.clone()), 4);
+        let metadata = contract.nft_token(token_id).unwrap().metadata.unwrap();
+        assert_eq!(metadata.title.unwrap(), "Mega Dragon".to_string());
+        contract.assert_invariants();
+    }
+
+    #[test]
+    fn test_registered_template_only_applies_to_its_species() {
+        let (mut context, mut contract) = setup_contract();
+        let mut template = sample_token_metadata();
+        template.title = Some("Golden Egg".to_string());
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.add_metadata("egg".to_string(), template);
+
+        testing_env!(context.attached_deposit(2 * MINIMUM_EARLY_DEPOSIT).build());
+        let first = contract.nft_mint_egg_species(accounts(1).to_string(), 1);
+        let second = contract.nft_mint_egg_species(accounts(1).to_string(), 2);
+
+        let title_of = |token_id: TokenId| contract.nft_token(token_id).unwrap().metadata.unwrap().title;
+        assert_eq!(title_of(first), Some("Golden Egg".to_string()));
+        assert_eq!(title_of(second), get_built_in_metadata(0, 2).unwrap().title);
+    }
+
+    #[test]
+    fn test_level_template_for_one_species_does_not_unblock_others() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.add_metadata("monster_4".to_string(), sample_token_metadata());
+
+        testing_env!(context.attached_deposit(2 * MINIMUM_EARLY_DEPOSIT).build());
+        let mut first = contract.nft_mint_egg_species(accounts(1).to_string(), 1);
+        let mut second = contract.nft_mint_egg_species(accounts(1).to_string(), 2);
+        for _ in 0..3 {
+            first = evolve(&mut context, &mut contract, accounts(1), first);
+            second = evolve(&mut context, &mut contract, accounts(1), second);
+        }
+
+        ready_to_evolve(&mut context, &contract, first.clone());
+        assert!(contract.evolve_blockers(first).is_empty());
+        ready_to_evolve(&mut context, &contract, second.clone());
+        assert_eq!(contract.evolve_blockers(second), vec!["Token has reached its maximum level".to_string()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "You have reach the maximum level of your monster")]
+    fn test_evolve_past_last_stage_without_template() {
+        let (mut context, mut contract) = setup_contract();
+
+        let mut token_id = mint_egg(&mut context, &mut contract, accounts(1));
+        for _ in 0..4 {
+            token_id = evolve(&mut context, &mut contract, accounts(1), token_id);
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,105 @@
+//! Seeded draws shared by every roll in the contract. Pure functions of their
+//! inputs, so other game contracts can reproduce a roll given the same seed.
+//! The seed must already carry the entropy (e.g. `env::random_seed()` or a
+//! revealed commit hash); the mixing here spreads it, it does not add any.
+
+/// Draws a `u64` from `seed`. Draw 0 is the seed's leading eight bytes read
+/// big-endian, so a short seed such as `[5]` reads as 5; any other `nonce`
+/// mixes that word with the nonce through splitmix64, giving one seed as many
+/// independent-looking draws as needed.
+pub fn seeded_u64(
+  seed: &[u8],
+  nonce: u64,
+) -> u64 {
+  let word = seed.iter().take(8).fold(0u64, |word, byte| (word << 8) | *byte as u64);
+  if nonce == 0 {
+    word
+  } else {
+    splitmix64(word ^ splitmix64(nonce))
+  }
+}
+
+/// Draws from `0..upper`. The modulo bias is below `upper / 2^64` for seeds of
+/// eight bytes or more, so any weight total a contract would use rolls evenly.
+pub fn in_range(
+  seed: &[u8],
+  nonce: u64,
+  upper: u64,
+) -> u64 {
+  assert!(upper > 0, "Range cannot be empty");
+  seeded_u64(seed, nonce) % upper
+}
+
+fn splitmix64(value: u64) -> u64 {
+  let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+  z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+  z ^ (z >> 31)
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_and_nonce_draw_the_same_value() {
+        let seed = b"nearmon-drop-1";
+        for nonce in 0..32 {
+            assert_eq!(seeded_u64(seed, nonce), seeded_u64(seed, nonce));
+            assert_eq!(in_range(seed, nonce, 16), in_range(seed, nonce, 16));
+        }
+    }
+
+    #[test]
+    fn test_first_draw_reads_leading_bytes() {
+        assert_eq!(seeded_u64(&[5], 0), 5);
+        assert_eq!(seeded_u64(&[1, 44], 0), 300);
+        assert_eq!(seeded_u64(&[0, 0, 0, 0, 0, 0, 0, 1, 0xff], 0), 1);
+        assert_eq!(seeded_u64(&[], 0), 0);
+        assert_eq!(in_range(&[1, 44], 0, 600), 300);
+    }
+
+    #[test]
+    fn test_nonces_give_distinct_draws() {
+        let draws: Vec<u64> = (0..64).map(|nonce| seeded_u64(&[5], nonce)).collect();
+        let mut unique = draws.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), draws.len());
+    }
+
+    #[test]
+    fn test_in_range_stays_below_upper() {
+        for nonce in 0..1_000 {
+            assert!(in_range(b"seed", nonce, 7) < 7);
+        }
+        assert_eq!(in_range(b"seed", 3, 1), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Range cannot be empty")]
+    fn test_in_range_rejects_empty_range() {
+        in_range(b"seed", 0, 0);
+    }
+
+    #[test]
+    fn test_draws_across_seeds_are_uniform() {
+        for upper in [7u64, 16, 600].iter() {
+            let draws = *upper * 1_000;
+            let mut buckets = vec![0u64; *upper as usize];
+            for index in 0..draws {
+                buckets[in_range(&index.to_be_bytes(), 1, *upper) as usize] += 1;
+            }
+            assert!(buckets.iter().all(|count| *count > 850 && *count < 1_150), "{:?}", buckets);
+        }
+    }
+
+    #[test]
+    fn test_draws_across_nonces_are_uniform() {
+        let mut buckets = [0u64; 16];
+        for nonce in 1..=16_000 {
+            buckets[in_range(b"nearmon", nonce, 16) as usize] += 1;
+        }
+        assert!(buckets.iter().all(|count| *count > 850 && *count < 1_150), "{:?}", buckets);
+    }
+}